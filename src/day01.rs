@@ -1,36 +1,82 @@
-pub fn parse_input(input: &str) -> Vec<u16> {
+use std::fmt;
+
+/// Spelled out digits recognized along the ascii ones
+#[derive(Debug, Clone, Default)]
+pub struct Words {
+    words: Vec<(String, u8)>,
+}
+
+impl Words {
+    /// Only ascii digits
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn english() -> Self {
+        [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ]
+        .into_iter()
+        .zip(1..)
+        .fold(Self::none(), |words, (w, d)| words.with(w, d))
+    }
+
+    pub fn with(mut self, word: &str, digit: u8) -> Self {
+        self.words.push((word.to_string(), digit));
+        self
+    }
+
+    /// Digit spelled at the start of `s`
+    fn digit_at(&self, s: &str) -> Option<u8> {
+        match s.as_bytes().first()? {
+            c @ b'0'..=b'9' => Some(c - b'0'),
+            _ => self
+                .words
+                .iter()
+                .find(|(w, _)| s.starts_with(w.as_str()))
+                .map(|&(_, d)| d),
+        }
+    }
+
+    /// First and last digits of the line, as a two digit number. Every
+    /// position is tried so overlapping words like "twone" both count.
+    pub fn calibration(&self, line: &str) -> Option<u16> {
+        let mut digits = line
+            .char_indices()
+            .filter_map(|(i, _)| self.digit_at(&line[i..]));
+        let first = digits.next()?;
+        let last = digits.next_back().unwrap_or(first);
+        Some(first as u16 * 10 + last as u16)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoDigit {
+    pub line: usize,
+}
+
+impl fmt::Display for NoDigit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No digit on line {}", self.line)
+    }
+}
+
+impl std::error::Error for NoDigit {}
+
+pub fn parse_with(input: &str, words: &Words) -> Result<Vec<u16>, NoDigit> {
     input
         .lines()
-        .map(|l| l.chars().filter(|c| c.is_ascii_digit()))
-        .map(|mut cs| {
-            let first = cs.next().unwrap();
-            let last = cs.last().unwrap_or(first);
-            format!("{}{}", first, last).parse().unwrap()
-        })
+        .enumerate()
+        .map(|(i, l)| words.calibration(l).ok_or(NoDigit { line: i + 1 }))
         .collect()
 }
+
+pub fn parse_input(input: &str) -> Vec<u16> {
+    parse_with(input, &Words::none()).unwrap_or_else(|e| panic!("invalid input: {e}"))
+}
+
 pub fn parse_input_p2(input: &str) -> Vec<u16> {
-    input
-        .lines()
-        .map(|l| {
-            l.to_string()
-                .replace("one", "one1one")
-                .replace("two", "two2two")
-                .replace("three", "three3three")
-                .replace("four", "four4four")
-                .replace("five", "five5five")
-                .replace("six", "six6six")
-                .replace("seven", "seven7seven")
-                .replace("eight", "eight8eight")
-                .replace("nine", "nine9nine")
-        })
-        .map(|l| l.chars().filter(|c| c.is_ascii_digit()).collect())
-        .map(|cs: Vec<char>| {
-            let first = cs.first().unwrap();
-            let last = cs.iter().last().unwrap_or(first);
-            format!("{}{}", first, last).parse().unwrap()
-        })
-        .collect()
+    parse_with(input, &Words::english()).unwrap_or_else(|e| panic!("invalid input: {e}"))
 }
 
 pub fn part1(input: Vec<u16>) -> u16 {
@@ -80,4 +126,44 @@ mod test {
         let result = part1(vec);
         assert_eq!(result, 281);
     }
+
+    #[test]
+    fn test_calibration() {
+        let words = Words::english();
+        assert_eq!(words.calibration("twone"), Some(21));
+        assert_eq!(words.calibration("eightwo"), Some(82));
+        assert_eq!(words.calibration("oneight"), Some(18));
+        assert_eq!(words.calibration("sevenine"), Some(79));
+        assert_eq!(words.calibration("x5y"), Some(55));
+        assert_eq!(words.calibration("zero"), None);
+        assert_eq!(Words::none().calibration("one2"), Some(22));
+        let words = Words::english().with("zero", 0);
+        assert_eq!(words.calibration("zero3"), Some(3));
+    }
+
+    #[test]
+    fn test_other_language() {
+        let french = [
+            "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+        ]
+        .into_iter()
+        .zip(1..)
+        .fold(Words::none(), |words, (w, d)| words.with(w, d));
+        assert_eq!(french.calibration("deuxxhuitrois"), Some(23));
+        assert_eq!(french.calibration("onetwo"), None);
+    }
+
+    #[test]
+    fn test_no_digit() {
+        let input = indoc! {
+        "1abc2
+        pqr3stu8vwx
+        abcdef
+        "};
+        assert_eq!(
+            parse_with(input, &Words::english()),
+            Err(NoDigit { line: 3 })
+        );
+        assert_eq!(NoDigit { line: 3 }.to_string(), "No digit on line 3");
+    }
 }
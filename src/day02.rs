@@ -1,17 +1,85 @@
 use anyhow::Result;
 use rustc_hash::FxHashMap as HashMap;
+use std::fmt;
 use std::str::FromStr;
 use winnow::{
-    ascii::digit1,
-    combinator::{alt, delimited, separated, terminated},
+    ascii::{alpha1, digit1},
+    combinator::{cut_err, delimited, separated, separated_pair},
     PResult, Parser,
 };
 
-pub type Draws = Vec<Vec<Color>>;
+/// Colours of the puzzle's cubes
+pub const COLORS: [&str; 3] = ["red", "green", "blue"];
+
+/// Number of cubes of each colour
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CubeSet {
+    counts: HashMap<String, u32>,
+}
+
+impl CubeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, color: &str, count: u32) -> Self {
+        self.counts.insert(color.to_string(), count);
+        self
+    }
+
+    /// A single draw, where each colour can appear only once
+    fn draw<'a>(cubes: impl IntoIterator<Item = (u32, &'a str)>) -> Result<Self, DuplicateColor> {
+        let mut set = CubeSet::new();
+        for (count, color) in cubes {
+            if set.counts.insert(color.to_string(), count).is_some() {
+                return Err(DuplicateColor(color.to_string()));
+            }
+        }
+        Ok(set)
+    }
+
+    pub fn get(&self, color: &str) -> u32 {
+        self.counts.get(color).copied().unwrap_or(0)
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts.iter().map(|(c, &n)| (c.as_str(), n))
+    }
+
+    pub fn is_subset_of(&self, other: &CubeSet) -> bool {
+        self.colors().all(|(c, n)| n <= other.get(c))
+    }
+
+    /// Smallest set containing both sets
+    pub fn union(mut self, other: &CubeSet) -> Self {
+        for (c, n) in other.colors() {
+            let count = self.counts.entry(c.to_string()).or_default();
+            *count = n.max(*count);
+        }
+        self
+    }
+
+    /// Product of the counts of `colors`, a colour not in the set counting
+    /// as 0
+    pub fn power(&self, colors: &[&str]) -> u32 {
+        colors.iter().map(|c| self.get(c)).product()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateColor(pub String);
+
+impl fmt::Display for DuplicateColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Duplicate colour {} in a draw", self.0)
+    }
+}
+
+impl std::error::Error for DuplicateColor {}
 
 pub struct Game {
     id: u32,
-    draws: Draws,
+    draws: Vec<CubeSet>,
 }
 
 impl FromStr for Game {
@@ -21,11 +89,27 @@ impl FromStr for Game {
         line.parse(s).map_err(|e| e.to_string())
     }
 }
-#[derive(Debug, PartialEq, Eq, Hash)]
-pub enum Color {
-    Red(u32),
-    Green(u32),
-    Blue(u32),
+
+impl Game {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn draws(&self) -> &[CubeSet] {
+        &self.draws
+    }
+
+    pub fn is_possible_with(&self, bag: &CubeSet) -> bool {
+        self.draws.iter().all(|d| d.is_subset_of(bag))
+    }
+
+    pub fn minimum_bag(&self) -> CubeSet {
+        self.draws.iter().fold(CubeSet::new(), CubeSet::union)
+    }
+
+    pub fn power(&self, colors: &[&str]) -> u32 {
+        self.minimum_bag().power(colors)
+    }
 }
 
 fn line(input: &mut &str) -> PResult<Game> {
@@ -33,12 +117,13 @@ fn line(input: &mut &str) -> PResult<Game> {
     Ok(Game { id, draws })
 }
 
-fn draws(input: &mut &str) -> PResult<Draws> {
+fn draws(input: &mut &str) -> PResult<Vec<CubeSet>> {
     separated(1.., colors, "; ").parse_next(input)
 }
 
-fn colors(input: &mut &str) -> PResult<Vec<Color>> {
-    separated(1.., color, ", ").parse_next(input)
+fn colors(input: &mut &str) -> PResult<CubeSet> {
+    cut_err(separated(1.., color, ", ").try_map(|cubes: Vec<_>| CubeSet::draw(cubes)))
+        .parse_next(input)
 }
 
 fn parse_game(input: &mut &str) -> PResult<u32> {
@@ -47,21 +132,8 @@ fn parse_game(input: &mut &str) -> PResult<u32> {
         .parse_next(input)
 }
 
-fn green(input: &mut &str) -> PResult<Color> {
-    let val = terminated(digit1, " green").parse_to().parse_next(input)?;
-    Ok(Color::Green(val))
-}
-fn red(input: &mut &str) -> PResult<Color> {
-    let val = terminated(digit1, " red").parse_to().parse_next(input)?;
-    Ok(Color::Red(val))
-}
-fn blue(input: &mut &str) -> PResult<Color> {
-    let val = terminated(digit1, " blue").parse_to().parse_next(input)?;
-    Ok(Color::Blue(val))
-}
-
-fn color(input: &mut &str) -> PResult<Color> {
-    alt((red, green, blue)).parse_next(input)
+fn color<'a>(input: &mut &'a str) -> PResult<(u32, &'a str)> {
+    separated_pair(digit1.parse_to(), " ", alpha1).parse_next(input)
 }
 
 pub fn parse_input(input: &str) -> Vec<Game> {
@@ -75,54 +147,19 @@ pub fn parse_input(input: &str) -> Vec<Game> {
 }
 
 pub fn part1(games: Vec<Game>) -> u32 {
+    let bag = CubeSet::new()
+        .with("red", 12)
+        .with("green", 13)
+        .with("blue", 14);
     games
         .into_iter()
-        .filter_map(|g| {
-            if g.draws.into_iter().all(|cs| {
-                cs.into_iter().all(|c| match c {
-                    Color::Red(r) => r <= 12,
-                    Color::Green(g) => g <= 13,
-                    Color::Blue(b) => b <= 14,
-                })
-            }) {
-                Some(g.id)
-            } else {
-                None
-            }
-        })
+        .filter(|g| g.is_possible_with(&bag))
+        .map(|g| g.id)
         .sum()
 }
+
 pub fn part2(games: Vec<Game>) -> u32 {
-    games
-        .into_iter()
-        .map(|g| {
-            let mut res = HashMap::from_iter([
-                (Color::Red(0), 0_u32),
-                (Color::Green(0), 0_u32),
-                (Color::Blue(0), 0_u32),
-            ]);
-            g.draws.into_iter().for_each(|cs| {
-                cs.into_iter().for_each(|c| match c {
-                    Color::Red(r) => {
-                        if let Some(x) = res.get_mut(&Color::Red(0)) {
-                            *x = r.max(*x)
-                        };
-                    }
-                    Color::Green(g) => {
-                        if let Some(x) = res.get_mut(&Color::Green(0)) {
-                            *x = g.max(*x)
-                        };
-                    }
-                    Color::Blue(b) => {
-                        if let Some(x) = res.get_mut(&Color::Blue(0)) {
-                            *x = b.max(*x)
-                        };
-                    }
-                });
-            });
-            res.values().product::<u32>()
-        })
-        .sum()
+    games.iter().map(|g| g.power(&COLORS)).sum()
 }
 
 #[cfg(test)]
@@ -153,6 +190,10 @@ mod test {
         "};
         let games = parse_input(input);
         assert_eq!(part2(games), 2286);
+        // a colour never shown makes the power 0
+        let games =
+            parse_input("Game 1: 3 blue, 4 red; 1 red, 6 blue\nGame 2: 1 red, 2 green, 3 blue");
+        assert_eq!(part2(games), 6);
     }
 
     #[test]
@@ -162,24 +203,14 @@ mod test {
         assert_eq!(result, 1);
     }
 
-    #[test]
-    fn test_green() {
-        let mut input = "1 green";
-        let result = green(&mut input);
-        assert_eq!(result, Ok(Color::Green(1)));
-    }
     #[test]
     fn test_color() {
         let mut input = "1 green";
         let result = color(&mut input);
-        assert_eq!(result, Ok(Color::Green(1)));
-    }
-
-    #[test]
-    fn test_color_red() {
-        let mut input = "11 red";
+        assert_eq!(result, Ok((1, "green")));
+        let mut input = "11 magenta";
         let result = color(&mut input);
-        assert_eq!(result, Ok(Color::Red(11)));
+        assert_eq!(result, Ok((11, "magenta")));
     }
 
     #[test]
@@ -188,9 +219,13 @@ mod test {
         let result = colors(&mut input);
         assert_eq!(
             result,
-            Ok(vec![Color::Red(11), Color::Blue(2), Color::Green(3)])
+            Ok(CubeSet::new()
+                .with("red", 11)
+                .with("blue", 2)
+                .with("green", 3))
         );
     }
+
     #[test]
     fn test_draws() {
         let mut input = "3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
@@ -198,13 +233,44 @@ mod test {
         assert_eq!(
             result,
             Ok(vec![
-                vec![Color::Blue(3), Color::Red(4)],
-                vec![Color::Red(1), Color::Green(2), Color::Blue(6)],
-                vec![Color::Green(2)]
+                CubeSet::new().with("blue", 3).with("red", 4),
+                CubeSet::new()
+                    .with("red", 1)
+                    .with("green", 2)
+                    .with("blue", 6),
+                CubeSet::new().with("green", 2)
             ])
         );
     }
 
+    #[test]
+    fn test_duplicate_color() {
+        let result = "Game 1: 3 blue; 1 red, 2 green, 6 red".parse::<Game>();
+        assert!(result
+            .err()
+            .is_some_and(|e| e.contains("Duplicate colour red in a draw")));
+    }
+
+    #[test]
+    fn test_game() {
+        let game: Game = "Game 7: 3 blue, 4 teal; 1 teal, 2 green, 6 blue; 2 green"
+            .parse()
+            .unwrap();
+        assert_eq!(game.id(), 7);
+        assert_eq!(game.draws().len(), 3);
+        let minimum = CubeSet::new()
+            .with("blue", 6)
+            .with("teal", 4)
+            .with("green", 2);
+        assert_eq!(game.minimum_bag(), minimum);
+        assert_eq!(game.power(&["blue", "teal", "green"]), 48);
+        assert_eq!(game.power(&COLORS), 0);
+        assert!(game.is_possible_with(&minimum));
+        assert!(game.is_possible_with(&minimum.clone().with("red", 1)));
+        assert!(!game.is_possible_with(&minimum.clone().with("teal", 3)));
+        assert!(!game.is_possible_with(&CubeSet::new().with("blue", 6).with("teal", 4)));
+    }
+
     // #[test]
     // fn test_bag() {
     //     let mut input = "12 green, 1 red, 2 blue";
@@ -1,10 +1,22 @@
+use itertools::Itertools;
 use num::complex::Complex;
-use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use rustc_hash::FxHashMap as HashMap;
 type Coord = Complex<isize>;
 
 type Symbols = HashMap<Coord, char>;
 type Numbers = Vec<BBox>;
 
+const NEIGHBOURS: [Coord; 8] = [
+    Complex::new(-1, -1),
+    Complex::new(0, -1),
+    Complex::new(1, -1),
+    Complex::new(-1, 0),
+    Complex::new(1, 0),
+    Complex::new(-1, 1),
+    Complex::new(0, 1),
+    Complex::new(1, 1),
+];
+
 #[derive(Debug, Hash, PartialEq, Eq)]
 pub struct BBox {
     left: Coord,
@@ -18,95 +30,128 @@ impl BBox {
             val: val.to_string(),
         }
     }
-    fn surroundings(&self) -> HashSet<Coord> {
-        let mut res = HashSet::default();
-        let right_limit = self.val.len() as isize + self.left.re;
-        for p in self.left.re..right_limit {
-            for j in -1..=1 {
-                for i in -1..=1 {
-                    if j == 0 && (self.left.re..right_limit).contains(&(p + i)) {
-                        continue;
-                    }
-                    res.insert(Complex::new(p + i, self.left.im + j));
-                }
-            }
-        }
-        res
+
+    pub fn left(&self) -> Coord {
+        self.left
+    }
+
+    pub fn value(&self) -> usize {
+        self.val.parse().unwrap()
+    }
+
+    /// Cells covered by the number
+    pub fn cells(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.val.len() as isize).map(|i| self.left + i)
     }
 
-    fn is_part_number(&self, symbols: &Symbols) -> bool {
-        self.surroundings().iter().any(|c| symbols.contains_key(c))
+    /// Cells around the number, each one once
+    pub fn surroundings(&self) -> impl Iterator<Item = Coord> + '_ {
+        let len = self.val.len() as isize;
+        let above_below = (-1..=len).flat_map(|i| [Complex::new(i, -1), Complex::new(i, 1)]);
+        [Complex::new(-1, 0), Complex::new(len, 0)]
+            .into_iter()
+            .chain(above_below)
+            .map(|c| self.left + c)
     }
 }
 
-pub fn parse_input(input: &str) -> (Symbols, Numbers) {
-    let mut symbols: HashMap<Complex<isize>, char> = HashMap::default();
-    let mut numbers = Vec::new();
-    input.lines().enumerate().for_each(|(j, line)| {
-        line.chars()
-            .enumerate()
-            .filter(|&(_, c)| !c.is_ascii_digit() && c != '.')
-            .for_each(|(i, c)| {
-                symbols.insert(Complex::new(i as isize, j as isize), c);
-            });
-        let mut val = String::new();
-        let mut start = None;
-        let mut prev: isize = -1;
-        for (i, c) in line
+/// Symbols and numbers of the engine, with the number covering each cell
+#[derive(Debug, Default)]
+pub struct Schematic {
+    symbols: Symbols,
+    numbers: Numbers,
+    cells: HashMap<Coord, usize>,
+}
+
+impl Schematic {
+    pub fn symbols(&self) -> &Symbols {
+        &self.symbols
+    }
+
+    pub fn numbers(&self) -> &[BBox] {
+        &self.numbers
+    }
+
+    pub fn number_at(&self, cell: Coord) -> Option<&BBox> {
+        self.cells.get(&cell).map(|&i| &self.numbers[i])
+    }
+
+    /// Numbers touching the cell, by order of appearance
+    pub fn numbers_near(&self, cell: Coord) -> Vec<&BBox> {
+        NEIGHBOURS
+            .iter()
+            .filter_map(|d| self.cells.get(&(cell + d)))
+            .sorted_unstable()
+            .dedup()
+            .map(|&i| &self.numbers[i])
+            .collect()
+    }
+
+    /// Symbols touching the number
+    pub fn symbols_near(&self, number: &BBox) -> Vec<(Coord, char)> {
+        number
+            .surroundings()
+            .filter_map(|c| self.symbols.get(&c).map(|&s| (c, s)))
+            .collect()
+    }
+
+    pub fn part_numbers(&self) -> impl Iterator<Item = &BBox> {
+        self.numbers
+            .iter()
+            .filter(|b| b.surroundings().any(|c| self.symbols.contains_key(&c)))
+    }
+
+    /// Symbols `symbol` touching exactly `neighbours` numbers, top to bottom
+    pub fn gears(&self, symbol: char, neighbours: usize) -> Vec<(Coord, Vec<&BBox>)> {
+        self.symbols
+            .iter()
+            .filter(|&(_, &c)| c == symbol)
+            .map(|(&pos, _)| (pos, self.numbers_near(pos)))
+            .filter(|(_, numbers)| numbers.len() == neighbours)
+            .sorted_unstable_by_key(|(pos, _)| (pos.im, pos.re))
+            .collect()
+    }
+}
+
+pub fn parse_input(input: &str) -> Schematic {
+    let mut schematic = Schematic::default();
+    for (j, line) in input.lines().enumerate() {
+        let runs = line
             .chars()
             .enumerate()
-            .filter(|&(_, c)| c.is_ascii_digit())
-        {
-            if start.is_none() {
-                start = Some(Complex::new(i as isize, j as isize));
-                val.push(c);
-                prev = i as isize;
-                continue;
-            }
-            if i as isize == prev + 1 && !val.is_empty() {
-                val.push(c);
-                prev += 1;
+            .group_by(|(_, c)| c.is_ascii_digit());
+        for (is_number, mut run) in &runs {
+            let Some((i, c)) = run.next() else { continue };
+            let pos = Complex::new(i as isize, j as isize);
+            if is_number {
+                let val = [c]
+                    .into_iter()
+                    .chain(run.map(|(_, c)| c))
+                    .collect::<String>();
+                let bbox = BBox::new(pos, &val);
+                let index = schematic.numbers.len();
+                schematic.cells.extend(bbox.cells().map(|c| (c, index)));
+                schematic.numbers.push(bbox);
             } else {
-                let bbox = BBox::new(start.unwrap(), &val);
-                numbers.push(bbox);
-                start = Some(Complex::new(i as isize, j as isize));
-                val = format!("{}", c);
-                prev = i as isize;
+                let symbols = [(i, c)].into_iter().chain(run).filter(|&(_, c)| c != '.');
+                schematic
+                    .symbols
+                    .extend(symbols.map(|(i, c)| (Complex::new(i as isize, j as isize), c)));
             }
         }
-        if !val.is_empty() {
-            let bbox = BBox::new(start.unwrap(), &val);
-            numbers.push(bbox);
-        }
-    });
-    (symbols, numbers)
+    }
+    schematic
 }
 
-pub fn part1((symbols, numbers): (Symbols, Numbers)) -> usize {
-    numbers
-        .into_iter()
-        .filter(|b| b.is_part_number(&symbols))
-        .map(|b| b.val.parse::<usize>().unwrap())
-        .sum()
+pub fn part1(schematic: Schematic) -> usize {
+    schematic.part_numbers().map(BBox::value).sum()
 }
 
-pub fn part2((symbols, numbers): (Symbols, Numbers)) -> usize {
-    let stars: Symbols = symbols.into_iter().filter(|(_, c)| *c == '*').collect();
-    let mut hm = HashMap::default();
-    for bbox in numbers {
-        for s in bbox.surroundings() {
-            if stars.contains_key(&s) {
-                hm.entry(s).or_insert(Vec::new()).push(bbox.val.to_string())
-            }
-        }
-    }
-    hm.values()
-        .filter(|v| v.len() == 2)
-        .map(|v| {
-            v.iter()
-                .map(|x| x.parse::<usize>().unwrap())
-                .product::<usize>()
-        })
+pub fn part2(schematic: Schematic) -> usize {
+    schematic
+        .gears('*', 2)
+        .into_iter()
+        .map(|(_, numbers)| numbers.into_iter().map(BBox::value).product::<usize>())
         .sum()
 }
 
@@ -115,11 +160,28 @@ mod test {
     use super::*;
     use indoc::indoc;
     use pretty_assertions::assert_eq;
+    use rustc_hash::FxHashSet as HashSet;
+
+    const EXAMPLE: &str = indoc! {
+        "
+        467..114..
+        ...*......
+        ..35..633.
+        ......#...
+        617*......
+        .....+.58.
+        ..592.....
+        ......755.
+        ...$.*....
+        .664.598.."
+    };
+
     #[test]
     fn test_surrondings() {
         let left = Complex::new(1, 1);
         let bbox = BBox::new(left, "123");
-        let s = bbox.surroundings();
+        let s = bbox.surroundings().collect::<HashSet<_>>();
+        assert_eq!(bbox.surroundings().count(), 12);
         assert_eq!(
             s,
             HashSet::from_iter([
@@ -153,7 +215,7 @@ mod test {
             ...$.*....
             .664.598.."
         };
-        let (symbols, numbers) = parse_input(input);
+        let schematic = parse_input(input);
         let expected_symbols = HashMap::from_iter([
             (Complex { re: 5, im: 8 }, '*'),
             (Complex { re: 3, im: 1 }, '*'),
@@ -162,7 +224,7 @@ mod test {
             (Complex { re: 3, im: 8 }, '$'),
             (Complex { re: 5, im: 5 }, '+'),
         ]);
-        assert_eq!(symbols, expected_symbols);
+        assert_eq!(schematic.symbols(), &expected_symbols);
         let expected_numbers = [
             BBox {
                 left: Complex { re: 0, im: 0 },
@@ -205,7 +267,7 @@ mod test {
                 val: "598".to_string(),
             },
         ];
-        assert_eq!(numbers, expected_numbers);
+        assert_eq!(schematic.numbers(), &expected_numbers);
     }
     #[test]
     fn test_par1() {
@@ -243,4 +305,65 @@ mod test {
         let result = part2(parse_input(input));
         assert_eq!(result, 467835);
     }
+
+    #[test]
+    fn test_number_at() {
+        let schematic = parse_input(EXAMPLE);
+        assert_eq!(
+            schematic.number_at(Complex::new(7, 0)).map(BBox::value),
+            Some(114)
+        );
+        assert_eq!(
+            schematic.number_at(Complex::new(5, 0)).map(BBox::value),
+            Some(114)
+        );
+        assert_eq!(schematic.number_at(Complex::new(4, 0)), None);
+        assert_eq!(schematic.number_at(Complex::new(3, 1)), None);
+    }
+
+    #[test]
+    fn test_adjacency() {
+        let schematic = parse_input(EXAMPLE);
+        let near = |x, y| {
+            schematic
+                .numbers_near(Complex::new(x, y))
+                .into_iter()
+                .map(BBox::value)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(near(3, 1), vec![467, 35]);
+        assert_eq!(near(3, 4), vec![617]);
+        assert_eq!(near(0, 3), vec![617]);
+        assert_eq!(near(9, 9), vec![]);
+        let number = schematic.number_at(Complex::new(6, 2)).unwrap();
+        assert_eq!(
+            schematic.symbols_near(number),
+            vec![(Complex::new(6, 3), '#')]
+        );
+        let number = schematic.number_at(Complex::new(7, 5)).unwrap();
+        assert_eq!(schematic.symbols_near(number), vec![]);
+        assert_eq!(schematic.part_numbers().count(), 8);
+    }
+
+    #[test]
+    fn test_gears() {
+        let schematic = parse_input(EXAMPLE);
+        let gears = |symbol, neighbours| {
+            schematic
+                .gears(symbol, neighbours)
+                .into_iter()
+                .map(|(pos, numbers)| (pos, numbers.into_iter().map(BBox::value).collect()))
+                .collect::<Vec<(Coord, Vec<usize>)>>()
+        };
+        assert_eq!(
+            gears('*', 2),
+            vec![
+                (Complex::new(3, 1), vec![467, 35]),
+                (Complex::new(5, 8), vec![755, 598])
+            ]
+        );
+        assert_eq!(gears('*', 1), vec![(Complex::new(3, 4), vec![617])]);
+        assert_eq!(gears('$', 1), vec![(Complex::new(3, 8), vec![664])]);
+        assert_eq!(gears('#', 2), vec![]);
+    }
 }
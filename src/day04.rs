@@ -1,5 +1,3 @@
-use std::collections::HashSet;
-
 use winnow::{
     ascii::{digit1, space0, space1},
     combinator::{delimited, separated, separated_pair},
@@ -44,39 +42,118 @@ fn score(len: usize) -> usize {
     }
 }
 
-fn num_winning(winning_numbers: &[u16], got_numbers: &[u16]) -> usize {
-    let g: HashSet<_> = HashSet::from_iter(got_numbers);
-    let w: HashSet<_> = HashSet::from_iter(winning_numbers);
-    Iterator::count(g.intersection(&w))
+/// Set of numbers below 128 as a bit mask
+fn mask(numbers: &[u16]) -> Result<u128, String> {
+    numbers.iter().try_fold(0, |acc, &n| match n {
+        0..=127 => Ok(acc | 1 << n),
+        _ => Err(format!("Number too large: {n}")),
+    })
 }
 
-pub fn part1(input: Vec<Cards>) -> usize {
-    input
-        .into_iter()
-        .map(|(_, (winning_numbers, got_numbers))| {
-            score(num_winning(&winning_numbers, &got_numbers))
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scratchcard {
+    id: u16,
+    winning: u128,
+    got: u128,
+}
+
+impl Scratchcard {
+    pub fn new(id: u16, winning: &[u16], got: &[u16]) -> Result<Self, String> {
+        Ok(Scratchcard {
+            id,
+            winning: mask(winning)?,
+            got: mask(got)?,
         })
-        .sum()
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn matches(&self) -> usize {
+        (self.winning & self.got).count_ones() as usize
+    }
 }
 
-pub fn part2(input: Vec<Cards>) -> usize {
-    let mut buffer = vec![1; input.len() + 1];
-    for (id, (winning_numbers, got_numbers)) in input.iter() {
-        let current_num = buffer[*id as usize];
-        let num_winning = num_winning(winning_numbers, got_numbers);
-        match num_winning {
-            0 => (),
-            n => {
-                let id = *id as usize;
-                buffer
-                    .iter_mut()
-                    .take(id + n + 1)
-                    .skip(id + 1)
-                    .for_each(|i| *i += current_num)
+/// Copies won by a card, and the cards that won them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ledger {
+    pub id: u16,
+    pub copies: usize,
+    pub won_from: Vec<(u16, usize)>,
+}
+
+impl Ledger {
+    /// The original card and its copies
+    pub fn instances(&self) -> usize {
+        1 + self.copies
+    }
+}
+
+/// Cards in table order, whatever their ids
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    cards: Vec<Scratchcard>,
+}
+
+impl Table {
+    pub fn new(cards: &[Cards]) -> Result<Self, String> {
+        let cards = cards
+            .iter()
+            .map(|(id, (winning, got))| Scratchcard::new(*id, winning, got))
+            .collect::<Result<_, _>>()?;
+        Ok(Table { cards })
+    }
+
+    pub fn cards(&self) -> &[Scratchcard] {
+        &self.cards
+    }
+
+    pub fn score(&self) -> usize {
+        self.cards.iter().map(|c| score(c.matches())).sum()
+    }
+
+    /// Copies won by each card, failing if a card wins copies of cards past
+    /// the end of the table
+    pub fn ledger(&self) -> Result<Vec<Ledger>, String> {
+        let mut ledger = self
+            .cards
+            .iter()
+            .map(|c| Ledger {
+                id: c.id,
+                copies: 0,
+                won_from: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        for (i, card) in self.cards.iter().enumerate() {
+            let won = card.matches();
+            if i + won >= self.cards.len() {
+                return Err(format!(
+                    "Card {} wins copies past the end of the table",
+                    card.id
+                ));
+            }
+            let instances = ledger[i].instances();
+            for entry in &mut ledger[i + 1..=i + won] {
+                entry.copies += instances;
+                entry.won_from.push((card.id, instances));
             }
         }
+        Ok(ledger)
     }
-    buffer.iter().skip(1).sum()
+}
+
+pub fn part1(input: Vec<Cards>) -> usize {
+    Table::new(&input).unwrap().score()
+}
+
+pub fn part2(input: Vec<Cards>) -> usize {
+    Table::new(&input)
+        .and_then(|table| table.ledger())
+        .unwrap()
+        .iter()
+        .map(Ledger::instances)
+        .sum()
 }
 
 #[cfg(test)]
@@ -173,4 +250,57 @@ mod test {
         let parsed = parse_input(input);
         assert_eq!(part2(parsed), 30);
     }
+
+    #[test]
+    fn test_scratchcard() {
+        let card = Scratchcard::new(1, &[41, 48, 83, 86, 17], &[83, 86, 6, 31, 17, 9, 48, 53]);
+        assert_eq!(card.map(|c| c.matches()), Ok(4));
+        assert_eq!(
+            Scratchcard::new(1, &[127, 0], &[0, 127]).map(|c| c.matches()),
+            Ok(2)
+        );
+        assert_eq!(
+            Scratchcard::new(1, &[128], &[]),
+            Err("Number too large: 128".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ledger() {
+        let input = indoc! {
+        "Card 7: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+        Card 3: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+        Card 12:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+        Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+        Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+        Card 1: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+        "};
+        let ledger = Table::new(&parse_input(input)).unwrap().ledger().unwrap();
+        assert_eq!(
+            ledger.iter().map(Ledger::instances).collect::<Vec<_>>(),
+            vec![1, 2, 4, 8, 14, 1]
+        );
+        assert_eq!(
+            ledger[3],
+            Ledger {
+                id: 4,
+                copies: 7,
+                won_from: vec![(7, 1), (3, 2), (12, 4)]
+            }
+        );
+        assert_eq!(ledger[0].won_from, vec![]);
+    }
+
+    #[test]
+    fn test_ledger_past_the_end() {
+        let input = indoc! {
+        "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+        Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+        "};
+        let table = Table::new(&parse_input(input)).unwrap();
+        assert_eq!(
+            table.ledger(),
+            Err("Card 1 wins copies past the end of the table".to_string())
+        );
+    }
 }
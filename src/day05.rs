@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use rustc_hash::FxHashMap as HashMap;
 use std::ops::Range;
 use winnow::ascii::{alpha1, digit1, line_ending, space1};
 use winnow::combinator::{preceded, separated, separated_pair, terminated};
@@ -18,67 +19,7 @@ impl Offset for Range<isize> {
         }
     }
 }
-trait Intersection {
-    fn intersect_remainder(
-        &self,
-        other: &Range<isize>,
-    ) -> (Option<Range<isize>>, Option<Vec<Range<isize>>>);
-}
-
-impl Intersection for Range<isize> {
-    /// intersection of the ranges, remainder of self
-    fn intersect_remainder(
-        &self,
-        other: &Range<isize>,
-    ) -> (Option<Range<isize>>, Option<Vec<Range<isize>>>) {
-        #![allow(clippy::single_range_in_vec_init)]
-        let (intersection, remainder);
-        // no intersection
-        if self.is_empty() || other.is_empty() || self.start >= other.end || self.end < other.start
-        {
-            intersection = None;
-            remainder = None;
-        } else if self.start < other.start {
-            // other included in self
-            if self.end > other.end {
-                intersection = Some(other.clone());
-                remainder = Some(vec![self.start..other.start, other.end..self.end]);
-            } else {
-                // self.end <= other.end
-                intersection = Some(other.start..self.end);
-                remainder = Some(vec![self.start..other.start]);
-            }
-        } else if self.start > other.start {
-            // self included in other
-            if self.end <= other.end {
-                intersection = Some(self.clone());
-                remainder = None;
-            } else {
-                //self.end > other.end
-                intersection = Some(self.start..other.end);
-                remainder = Some(vec![other.end..self.end])
-            }
-        } else {
-            // self.start == other.start
-            if self.end <= other.end {
-                intersection = Some(self.clone());
-                remainder = None;
-            } else {
-                //self.end > other.end
-                intersection = Some(self.start..other.end);
-                remainder = Some(vec![other.end..self.end]);
-            }
-        }
-        if let Some(int) = &intersection {
-            if int.is_empty() {
-                return (None, None);
-            }
-        }
-        (intersection, remainder)
-    }
-}
-
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Mapping {
     source: Range<isize>,
     offset: isize,
@@ -127,9 +68,173 @@ pub fn parse_input(input: &str) -> (Vec<isize>, Vec<Map>) {
         .unwrap()
 }
 
-fn next_parts(parts: &Vec<isize>, maps: &Vec<Mapping>) -> Vec<isize> {
+/// Mappings of each block from `from` to `to`, following the block names
+fn chain<'a>(maps: &'a [Map], from: &str, to: &str) -> Result<Vec<&'a [Mapping]>, String> {
+    let by_source: HashMap<&str, (&str, &[Mapping])> = maps
+        .iter()
+        .map(|(source, dest, mappings)| (source.as_str(), (dest.as_str(), mappings.as_slice())))
+        .collect();
+    let mut res = Vec::new();
+    let mut current = from;
+    while current != to {
+        if res.len() >= maps.len() {
+            return Err(format!("Cycle from {from} without reaching {to}"));
+        }
+        let (dest, mappings) = by_source
+            .get(current)
+            .ok_or_else(|| format!("No map from {current}"))?;
+        res.push(*mappings);
+        current = dest;
+    }
+    Ok(res)
+}
+
+/// Piecewise offset over the whole line. Each piece starts at its key and
+/// runs to the next one, the line is left unchanged before the first piece.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntervalMap {
+    pieces: Vec<(isize, isize)>,
+}
+
+impl IntervalMap {
+    /// The first mapping containing a value wins, like in the almanac
+    pub fn new(mappings: &[Mapping]) -> Self {
+        let bounds = mappings.iter().flat_map(|m| [m.source.start, m.source.end]);
+        Self::from_bounds(bounds, |x| {
+            mappings
+                .iter()
+                .find(|m| m.source.contains(&x))
+                .map_or(0, |m| m.offset)
+        })
+    }
+
+    /// Pieces starting at each bound, normalized so that consecutive pieces
+    /// have different offsets
+    fn from_bounds(bounds: impl Iterator<Item = isize>, offset: impl Fn(isize) -> isize) -> Self {
+        let mut pieces: Vec<(isize, isize)> = Vec::new();
+        for start in bounds.sorted_unstable().dedup() {
+            let offset = offset(start);
+            let previous = pieces.last().map_or(0, |&(_, o)| o);
+            if offset != previous {
+                pieces.push((start, offset));
+            }
+        }
+        IntervalMap { pieces }
+    }
+
+    /// Composition of the maps along the chain of blocks from `from` to `to`
+    pub fn chain(maps: &[Map], from: &str, to: &str) -> Result<Self, String> {
+        Ok(chain(maps, from, to)?
+            .into_iter()
+            .map(IntervalMap::new)
+            .fold(IntervalMap::default(), |acc, m| acc.then(&m)))
+    }
+
+    pub fn offset(&self, x: isize) -> isize {
+        match self.pieces.partition_point(|&(start, _)| start <= x) {
+            0 => 0,
+            i => self.pieces[i - 1].1,
+        }
+    }
+
+    pub fn get(&self, x: isize) -> isize {
+        x + self.offset(x)
+    }
+
+    /// Map applying self, then `other`
+    pub fn then(&self, other: &IntervalMap) -> IntervalMap {
+        // other's bounds, pulled back through every offset of self, are a
+        // superset of the bounds of the composition
+        let offsets = self.pieces.iter().map(|&(_, o)| o).chain([0]);
+        let pulled = offsets
+            .cartesian_product(other.pieces.iter().map(|&(start, _)| start))
+            .map(|(o, start)| start - o);
+        let bounds = self.pieces.iter().map(|&(start, _)| start).chain(pulled);
+        Self::from_bounds(bounds, |x| {
+            let offset = self.offset(x);
+            offset + other.offset(x + offset)
+        })
+    }
+
+    /// Image of the ranges, sorted with overlapping ranges merged
+    pub fn map_ranges(&self, ranges: &[Range<isize>]) -> Vec<Range<isize>> {
+        let mut res = Vec::new();
+        for range in ranges.iter().filter(|r| !r.is_empty()) {
+            let inner = self
+                .pieces
+                .iter()
+                .map(|&(start, _)| start)
+                .filter(|start| range.contains(start));
+            let mut bounds = [range.start].into_iter().chain(inner).peekable();
+            while let Some(start) = bounds.next() {
+                let end = bounds.peek().copied().unwrap_or(range.end);
+                res.push((start..end).offset(self.offset(start)));
+            }
+        }
+        merge(res)
+    }
+
+    /// Every piece with its offset, the first and last ones unbounded
+    fn segments(&self) -> impl Iterator<Item = (Range<isize>, isize)> + '_ {
+        let starts = self.pieces.iter().map(|&(start, _)| start);
+        let offsets = self.pieces.iter().map(|&(_, offset)| offset);
+        itertools::izip!(
+            [isize::MIN].into_iter().chain(starts.clone()),
+            starts.chain([isize::MAX]),
+            [0].into_iter().chain(offsets)
+        )
+        .map(|(start, end, offset)| (start..end, offset))
+    }
+
+    /// Values mapped into `range`, sorted with overlapping ranges merged
+    pub fn preimage(&self, range: &Range<isize>) -> Vec<Range<isize>> {
+        let res = self
+            .segments()
+            .map(|(segment, offset)| {
+                segment.start.max(range.start.saturating_sub(offset))
+                    ..segment.end.min(range.end.saturating_sub(offset))
+            })
+            .filter(|r| !r.is_empty())
+            .collect();
+        merge(res)
+    }
+
+    /// Lowest value reached from `ranges`, with the parts of `ranges` mapped
+    /// to it
+    pub fn lowest(&self, ranges: &[Range<isize>]) -> Option<(isize, Vec<Range<isize>>)> {
+        let lowest = self.map_ranges(ranges).first()?.start;
+        let sources = self
+            .preimage(&(lowest..lowest + 1))
+            .into_iter()
+            .flat_map(|p| {
+                ranges
+                    .iter()
+                    .map(move |r| p.start.max(r.start)..p.end.min(r.end))
+            })
+            .filter(|r| !r.is_empty())
+            .collect();
+        Some((lowest, merge(sources)))
+    }
+}
+
+/// Sorts the ranges and merges the overlapping or touching ones
+fn merge(mut ranges: Vec<Range<isize>>) -> Vec<Range<isize>> {
+    ranges.sort_unstable_by_key(|r| r.start);
+    ranges
+        .into_iter()
+        .coalesce(|a, b| {
+            if b.start <= a.end {
+                Ok(a.start..a.end.max(b.end))
+            } else {
+                Err((a, b))
+            }
+        })
+        .collect()
+}
+
+fn next_parts(parts: &[isize], maps: &[Mapping]) -> Vec<isize> {
     let mut res = Vec::with_capacity(parts.len());
-    let mut parts = parts.clone();
+    let mut parts = parts.to_vec();
     for m in maps {
         parts.retain(|part| {
             if m.source.contains(part) {
@@ -144,8 +249,8 @@ fn next_parts(parts: &Vec<isize>, maps: &Vec<Mapping>) -> Vec<isize> {
 
 pub fn part1((seeds, maps): (Vec<isize>, Vec<Map>)) -> isize {
     let mut new_parts = seeds.clone();
-    for (_, _, map) in maps {
-        new_parts = next_parts(&new_parts, &map);
+    for map in chain(&maps, "seed", "location").unwrap() {
+        new_parts = next_parts(&new_parts, map);
     }
     if let Some(&m) = new_parts.iter().min() {
         m
@@ -154,52 +259,14 @@ pub fn part1((seeds, maps): (Vec<isize>, Vec<Map>)) -> isize {
     }
 }
 
-fn next_parts_p2(parts: &[Range<isize>], maps: &Vec<Mapping>) -> Vec<Range<isize>> {
-    let mut res = Vec::new();
-    let mut parts = parts.to_owned();
-
-    while let Some(current_part) = parts.pop() {
-        let mut intersect = false;
-        for m in maps {
-            match current_part.intersect_remainder(&m.source) {
-                (None, _) => {
-                    continue;
-                }
-                (Some(x), None) => {
-                    res.push(x.offset(m.offset));
-                    intersect = true;
-                    break;
-                }
-                (Some(x), Some(rem)) => {
-                    res.push(x.offset(m.offset));
-                    for r in rem {
-                        if !r.is_empty() {
-                            parts.push(r);
-                        }
-                    }
-                    intersect = true;
-                    break;
-                }
-            }
-        }
-        if !intersect {
-            res.push(current_part);
-        }
-    }
-
-    res
-}
-
 pub fn part2((seeds, maps): (Vec<isize>, Vec<Map>)) -> isize {
-    let mut new_seeds = Vec::new();
-    let mut iter = seeds.clone().into_iter();
-    while let Some((x, y)) = iter.next_tuple() {
-        new_seeds.push(x..x + y);
-    }
-    for (_, _, map) in maps {
-        new_seeds = next_parts_p2(&new_seeds, &map);
-    }
-    if let Some(m) = new_seeds.iter().min_by_key(|x| x.start) {
+    let seeds = seeds
+        .into_iter()
+        .tuples()
+        .map(|(x, y)| x..x + y)
+        .collect::<Vec<_>>();
+    let almanac = IntervalMap::chain(&maps, "seed", "location").unwrap();
+    if let Some(m) = almanac.map_ranges(&seeds).first() {
         m.start
     } else {
         panic!("no result")
@@ -214,33 +281,6 @@ mod tests {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
-    #[test]
-    fn test_intersect_remainder() {
-        let me = 0isize..2;
-        let other = 0isize..2;
-        assert_eq!(me.intersect_remainder(&other), (Some(0..2), None));
-        let me = 0isize..1;
-        let other = 0isize..2;
-        assert_eq!(me.intersect_remainder(&other), (Some(0..1), None));
-        let me = 0isize..3;
-        let other = 0isize..2;
-        assert_eq!(
-            me.intersect_remainder(&other),
-            (Some(0..2), Some(vec![2..3]))
-        );
-        let me = -1isize..3;
-        let other = 0isize..2;
-        assert_eq!(
-            me.intersect_remainder(&other),
-            (Some(0..2), Some(vec![-1..0, 2..3]))
-        );
-        let me = 2isize..3;
-        let other = 0isize..2;
-        assert_eq!(me.intersect_remainder(&other), (None, None));
-        let me = 2isize..3;
-        let other = 0isize..1;
-        assert_eq!(me.intersect_remainder(&other), (None, None));
-    }
     #[test]
     fn test_offset() {
         let me = 0isize..2;
@@ -474,4 +514,141 @@ mod tests {
         ];
         assert_eq!(parse_input(input), (vec![79, 14, 55, 13], expected));
     }
+
+    const EXAMPLE: &str = indoc! {
+      "seeds: 79 14 55 13
+
+      seed-to-soil map:
+      50 98 2
+      52 50 48
+
+      soil-to-fertilizer map:
+      0 15 37
+      37 52 2
+      39 0 15
+
+      fertilizer-to-water map:
+      49 53 8
+      0 11 42
+      42 0 7
+      57 7 4
+
+      water-to-light map:
+      88 18 7
+      18 25 70
+
+      light-to-temperature map:
+      45 77 23
+      81 45 19
+      68 64 13
+
+      temperature-to-humidity map:
+      0 69 1
+      1 0 69
+
+      humidity-to-location map:
+      60 56 37
+      56 93 4"
+    };
+
+    #[test]
+    fn test_interval_map() {
+        let map = IntervalMap::new(&[Mapping::new(98..100, -48), Mapping::new(50..98, 2)]);
+        assert_eq!(map.pieces, vec![(50, 2), (98, -48), (100, 0)]);
+        assert_eq!(
+            [0, 49, 50, 97, 98, 99, 100].map(|x| map.get(x)),
+            [0, 49, 52, 99, 50, 51, 100]
+        );
+        // the first mapping wins on overlaps, identity pieces are dropped
+        let map = IntervalMap::new(&[Mapping::new(0..10, 1), Mapping::new(5..20, 1)]);
+        assert_eq!(map.pieces, vec![(0, 1), (20, 0)]);
+        let map = IntervalMap::new(&[Mapping::new(0..10, 0)]);
+        assert_eq!(map, IntervalMap::default());
+    }
+
+    #[test]
+    fn test_interval_map_then() {
+        let (_, maps) = parse_input(EXAMPLE);
+        let almanac = IntervalMap::chain(&maps, "seed", "location").unwrap();
+        for seed in -5..120 {
+            let expected = maps
+                .iter()
+                .fold(vec![seed], |parts, (_, _, m)| next_parts(&parts, m));
+            assert_eq!(almanac.get(seed), expected[0], "seed {seed}");
+        }
+        let shift = IntervalMap::new(&[Mapping::new(0..10, 10)]);
+        let back = IntervalMap::new(&[Mapping::new(10..20, -10)]);
+        // 0..10 goes back to itself, 10..20 is only shifted by the second map
+        assert_eq!(shift.then(&back).pieces, vec![(10, -10), (20, 0)]);
+    }
+
+    #[test]
+    fn test_map_ranges() {
+        let map = IntervalMap::new(&[Mapping::new(98..100, -48), Mapping::new(50..98, 2)]);
+        assert_eq!(map.map_ranges(&[79..93, 55..68]), vec![57..70, 81..95]);
+        assert_eq!(map.map_ranges(&[90..95, 95..102]), vec![50..52, 92..102]);
+        assert_eq!(map.map_ranges(&[96..100, 0..0]), vec![50..52, 98..100]);
+        assert_eq!(map.map_ranges(&[40..50, 45..55]), vec![40..50, 52..57]);
+    }
+
+    #[test]
+    fn test_chain_shuffled() {
+        let (seeds, mut maps) = parse_input(EXAMPLE);
+        maps.reverse();
+        maps.swap(1, 4);
+        assert_eq!(part1((seeds.clone(), maps.clone())), 35);
+        assert_eq!(part2((seeds, maps.clone())), 46);
+        assert_eq!(
+            IntervalMap::chain(&maps, "seed", "water").unwrap().get(79),
+            81
+        );
+        assert_eq!(
+            IntervalMap::chain(&maps, "soil", "seed"),
+            Err("No map from location".to_string())
+        );
+        let cycle = vec![
+            ("a".to_string(), "b".to_string(), vec![]),
+            ("b".to_string(), "a".to_string(), vec![]),
+        ];
+        assert_eq!(
+            IntervalMap::chain(&cycle, "a", "c"),
+            Err("Cycle from a without reaching c".to_string())
+        );
+    }
+
+    #[test]
+    fn test_preimage() {
+        let map = IntervalMap::new(&[Mapping::new(98..100, -48), Mapping::new(50..98, 2)]);
+        assert_eq!(map.preimage(&(50..52)), vec![98..100]);
+        assert_eq!(map.preimage(&(40..60)), vec![40..58, 98..100]);
+        assert_eq!(map.preimage(&(100..101)), vec![100..101]);
+        assert_eq!(map.preimage(&(5..5)), vec![]);
+        // not injective: 0..10 and 10..20 both land on 10..20, the rest is left as is
+        let map = IntervalMap::new(&[Mapping::new(0..10, 10), Mapping::new(10..20, 0)]);
+        assert_eq!(map.preimage(&(15..25)), vec![5..10, 15..25]);
+    }
+
+    #[test]
+    fn test_lowest() {
+        #![allow(clippy::single_range_in_vec_init)]
+        let (seeds, maps) = parse_input(EXAMPLE);
+        let almanac = IntervalMap::chain(&maps, "seed", "location").unwrap();
+        let seeds = seeds
+            .into_iter()
+            .tuples()
+            .map(|(x, y)| x..x + y)
+            .collect::<Vec<_>>();
+        assert_eq!(almanac.lowest(&seeds), Some((46, vec![82..83])));
+        assert_eq!(almanac.get(82), 46);
+        for location in 0..100 {
+            for seed in almanac
+                .preimage(&(location..location + 1))
+                .into_iter()
+                .flatten()
+            {
+                assert_eq!(almanac.get(seed), location);
+            }
+        }
+        assert_eq!(almanac.lowest(&[]), None);
+    }
 }
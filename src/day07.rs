@@ -1,182 +1,225 @@
-use std::collections::HashSet;
-
-use anyhow::Result;
 use itertools::Itertools;
 
-#[derive(Debug, PartialOrd, Ord, Eq, PartialEq, Hash, Clone, Copy)]
-pub enum Card {
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
+/// Where wild cards rank when breaking ties
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WildRank {
+    /// at their place in the ordering
+    Natural,
+    Lowest,
+    Highest,
+}
+
+/// Card ordering and wildcard for a variant of Camel Cards
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rules {
+    /// labels from the weakest to the strongest card
+    order: String,
+    wild: Option<char>,
+    wild_rank: WildRank,
 }
 
-impl std::str::FromStr for Card {
-    type Err = String;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "2" => Ok(Card::Two),
-            "3" => Ok(Card::Three),
-            "4" => Ok(Card::Four),
-            "5" => Ok(Card::Five),
-            "6" => Ok(Card::Six),
-            "7" => Ok(Card::Seven),
-            "8" => Ok(Card::Eight),
-            "9" => Ok(Card::Nine),
-            "T" => Ok(Card::Ten),
-            "J" => Ok(Card::Jack),
-            "Q" => Ok(Card::Queen),
-            "K" => Ok(Card::King),
-            "A" => Ok(Card::Ace),
-            _ => Err("Invalid card".to_string()),
+impl Rules {
+    pub fn new(order: &str, wild: Option<char>, wild_rank: WildRank) -> Self {
+        Rules {
+            order: order.to_string(),
+            wild,
+            wild_rank,
         }
     }
+
+    /// Part 1 rules, no wildcard
+    pub fn standard() -> Self {
+        Rules::new("23456789TJQKA", None, WildRank::Natural)
+    }
+
+    /// Part 2 rules, jacks are jokers and the weakest card
+    pub fn jokers() -> Self {
+        Rules::new("23456789TJQKA", Some('J'), WildRank::Lowest)
+    }
+
+    pub fn card(&self, label: char) -> Result<Card, String> {
+        let position = self
+            .order
+            .chars()
+            .position(|c| c == label)
+            .ok_or_else(|| format!("Invalid card: {label}"))?;
+        let wild = self.wild == Some(label);
+        let strength = match (wild, self.wild_rank) {
+            (true, WildRank::Lowest) => 0,
+            (true, WildRank::Highest) => u8::MAX,
+            _ => position as u8 + 1,
+        };
+        Ok(Card {
+            strength,
+            wild,
+            label,
+            suit: None,
+        })
+    }
+
+    pub fn cards(&self, labels: &str) -> Result<Vec<Card>, String> {
+        labels.chars().map(|c| self.card(c)).collect()
+    }
+
+    /// Cards written as label then suit, like `AhTs`
+    pub fn suited_cards(&self, cards: &str) -> Result<Vec<Card>, String> {
+        let chars = cards.chars().collect::<Vec<_>>();
+        chars
+            .chunks(2)
+            .map(|c| match c {
+                [label, suit] => Ok(Card {
+                    suit: Some(*suit),
+                    ..self.card(*label)?
+                }),
+                _ => Err(format!("No suit for card {}", c[0])),
+            })
+            .collect()
+    }
 }
 
+/// A card whose strength depends on the rules it was parsed with
 #[derive(Debug, PartialOrd, Ord, Eq, PartialEq, Hash, Clone, Copy)]
-pub enum Card2 {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Queen,
-    King,
-    Ace,
+pub struct Card {
+    strength: u8,
+    wild: bool,
+    label: char,
+    suit: Option<char>,
 }
 
-impl std::str::FromStr for Card2 {
-    type Err = String;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "2" => Ok(Card2::Two),
-            "3" => Ok(Card2::Three),
-            "4" => Ok(Card2::Four),
-            "5" => Ok(Card2::Five),
-            "6" => Ok(Card2::Six),
-            "7" => Ok(Card2::Seven),
-            "8" => Ok(Card2::Eight),
-            "9" => Ok(Card2::Nine),
-            "T" => Ok(Card2::Ten),
-            "J" => Ok(Card2::Joker),
-            "Q" => Ok(Card2::Queen),
-            "K" => Ok(Card2::King),
-            "A" => Ok(Card2::Ace),
-            _ => Err("Invalid card".to_string()),
+impl Card {
+    pub fn label(&self) -> char {
+        self.label
+    }
+
+    pub fn is_wild(&self) -> bool {
+        self.wild
+    }
+
+    pub fn suit(&self) -> Option<char> {
+        self.suit
+    }
+}
+
+/// Group sizes of a hand, largest first, wild cards joining the largest
+/// group. Comparing signatures ranks hands of any size.
+#[derive(Debug, Clone, PartialOrd, Ord, Eq, PartialEq, Hash)]
+pub struct Signature(pub Vec<usize>);
+
+impl Signature {
+    pub fn new(cards: &[Card]) -> Self {
+        let wilds = cards.iter().filter(|c| c.wild).count();
+        let mut counts = cards
+            .iter()
+            .filter(|c| !c.wild)
+            .counts_by(|c| c.label)
+            .into_values()
+            .collect::<Vec<_>>();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        // joining the largest group is always the best hand
+        match counts.first_mut() {
+            Some(largest) => *largest += wilds,
+            None if wilds > 0 => counts.push(wilds),
+            None => (),
         }
+        Signature(counts)
     }
 }
 
-#[derive(Debug, PartialOrd, Ord, Eq, PartialEq, Hash)]
-pub enum Hand {
-    HighCard,
-    Pair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
+/// Extra category, like a flush, ranked just above the hands with signature
+/// `above`
+#[derive(Debug, Clone)]
+pub struct Category {
+    pub name: String,
+    pub above: Signature,
+    pub matches: fn(&[Card]) -> bool,
+}
+
+/// All the cards have the same suit
+pub fn is_flush(cards: &[Card]) -> bool {
+    cards
+        .iter()
+        .map(|c| c.suit)
+        .all_equal_value()
+        .is_ok_and(|s| s.is_some())
+}
+
+#[derive(Debug, Clone, PartialOrd, Ord, Eq, PartialEq, Hash)]
+pub struct Hand {
+    /// signature used for ranking, the one of the category if any
+    rank: Signature,
+    /// 0 for plain hands, 1 + the index of the category otherwise
+    category: usize,
+    signature: Signature,
 }
 
 impl Hand {
-    fn new(cards: Vec<Card>) -> Self {
-        let mut cards = cards;
-        cards.sort();
-        let mut map: Vec<usize> = cards
-            .into_iter()
-            .group_by(move |&c| c)
-            .into_iter()
-            .map(|(_, g)| g.collect::<Vec<_>>().len())
-            .collect();
-        map.sort();
-        let mut map_iter = map.iter();
-        match map_iter.next_back() {
-            Some(1) => Hand::HighCard,
-            Some(4) => Hand::FourOfAKind,
-            Some(5) => Hand::FiveOfAKind,
-            Some(2) => match map_iter.next_back() {
-                Some(2) => Hand::TwoPair,
-                Some(1) => Hand::Pair,
-                _ => panic!("weird hand"),
-            },
-            Some(3) => match map_iter.next_back() {
-                Some(2) => Hand::FullHouse,
-                Some(1) => Hand::ThreeOfAKind,
-                _ => panic!("weird hand"),
-            },
-
-            _ => panic!("weird hand"),
+    /// Classic Camel Cards hand, without extra categories
+    pub fn new(cards: &[Card]) -> Self {
+        Classifier::default().classify(cards)
+    }
+
+    pub fn from_signature(signature: &[usize]) -> Self {
+        Hand {
+            rank: Signature(signature.to_vec()),
+            category: 0,
+            signature: Signature(signature.to_vec()),
         }
     }
-}
 
-impl std::str::FromStr for Hand {
-    type Err = String;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let cards = s.chars().map(|c| c.to_string().parse().unwrap()).collect();
-        Ok(Hand::new(cards))
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Index of the extra category of the hand in its classifier
+    pub fn category(&self) -> Option<usize> {
+        self.category.checked_sub(1)
     }
 }
 
-pub fn parse_input(input: &str) -> Vec<(Hand, Vec<Card>, usize)> {
-    input
-        .lines()
-        .map(|l| {
-            if let Some((cards_str, bid)) = l.split_once(' ') {
-                let cards: Vec<Card> = cards_str
-                    .chars()
-                    .map(|c| c.to_string().parse().unwrap())
-                    .collect();
-                let hand = Hand::new(cards.clone());
-                (hand, cards, bid.parse().unwrap())
-            } else {
-                panic!("no space")
-            }
-        })
-        .collect()
+/// Ranks hands by signature, plus the extra categories registered, later
+/// categories ranking higher
+#[derive(Debug, Clone, Default)]
+pub struct Classifier {
+    categories: Vec<Category>,
 }
 
-fn replace_joker(original_cards: &str) -> Hand {
-    if !original_cards.contains('J') {
-        return original_cards.parse().unwrap();
+impl Classifier {
+    pub fn with_category(mut self, category: Category) -> Self {
+        self.categories.push(category);
+        self
     }
-    let to_replace: HashSet<_> = original_cards.chars().filter(|x| x != &'J').collect();
-    if to_replace.is_empty() {
-        return Hand::FiveOfAKind;
+
+    pub fn categories(&self) -> &[Category] {
+        &self.categories
     }
-    let mut current_max = Hand::HighCard;
-    for c in to_replace.into_iter().unique() {
-        let cards = original_cards.to_owned();
-        let h = cards.replace('J', &c.to_string()).parse().unwrap();
-        current_max = current_max.max(h);
+
+    pub fn classify(&self, cards: &[Card]) -> Hand {
+        let signature = Signature::new(cards);
+        let plain = Hand {
+            rank: signature.clone(),
+            category: 0,
+            signature: signature.clone(),
+        };
+        self.categories
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| (c.matches)(cards))
+            .map(|(i, c)| Hand {
+                rank: c.above.clone(),
+                category: i + 1,
+                signature: signature.clone(),
+            })
+            .fold(plain, Hand::max)
     }
-    current_max
 }
 
-pub fn parse_input_p2(input: &str) -> Vec<(Hand, Vec<Card2>, usize)> {
+pub fn parse_with(input: &str, rules: &Rules) -> Vec<(Hand, Vec<Card>, usize)> {
     input
         .lines()
         .map(|l| {
             if let Some((cards_str, bid)) = l.split_once(' ') {
-                let cards: Vec<Card2> = cards_str
-                    .chars()
-                    .map(|c| c.to_string().parse().unwrap())
-                    .collect();
-                let hand = replace_joker(cards_str);
+                let cards = rules.cards(cards_str).unwrap();
+                let hand = Hand::new(&cards);
                 (hand, cards, bid.parse().unwrap())
             } else {
                 panic!("no space")
@@ -185,16 +228,15 @@ pub fn parse_input_p2(input: &str) -> Vec<(Hand, Vec<Card2>, usize)> {
         .collect()
 }
 
-pub fn part1(hands_and_bids: Vec<(Hand, Vec<Card>, usize)>) -> usize {
-    let mut hands_and_bids = hands_and_bids;
-    hands_and_bids.sort();
-    hands_and_bids
-        .iter()
-        .enumerate()
-        .map(|(rank, (_, _, bid))| (rank + 1) * bid)
-        .sum()
+pub fn parse_input(input: &str) -> Vec<(Hand, Vec<Card>, usize)> {
+    parse_with(input, &Rules::standard())
 }
-pub fn part2(hands_and_bids: Vec<(Hand, Vec<Card2>, usize)>) -> usize {
+
+pub fn parse_input_p2(input: &str) -> Vec<(Hand, Vec<Card>, usize)> {
+    parse_with(input, &Rules::jokers())
+}
+
+fn winnings(hands_and_bids: Vec<(Hand, Vec<Card>, usize)>) -> usize {
     let mut hands_and_bids = hands_and_bids;
     hands_and_bids.sort();
     hands_and_bids
@@ -204,12 +246,24 @@ pub fn part2(hands_and_bids: Vec<(Hand, Vec<Card2>, usize)>) -> usize {
         .sum()
 }
 
+pub fn part1(hands_and_bids: Vec<(Hand, Vec<Card>, usize)>) -> usize {
+    winnings(hands_and_bids)
+}
+
+pub fn part2(hands_and_bids: Vec<(Hand, Vec<Card>, usize)>) -> usize {
+    winnings(hands_and_bids)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
+    fn cards(labels: &str) -> Vec<Card> {
+        Rules::standard().cards(labels).unwrap()
+    }
+
     #[test]
     fn test_part2() {
         let input = indoc! {
@@ -238,7 +292,6 @@ mod test {
     }
     #[test]
     fn test_parse_input() {
-        use super::Card::*;
         let input = indoc! {
             "32T3K 765
             T55J5 684
@@ -249,71 +302,126 @@ mod test {
         };
         let parsed = parse_input(input);
         let expected = vec![
-            (Hand::Pair, vec![Three, Two, Ten, Three, King], 765),
-            (Hand::ThreeOfAKind, vec![Ten, Five, Five, Jack, Five], 684),
-            (Hand::TwoPair, vec![King, King, Six, Seven, Seven], 28),
-            (Hand::TwoPair, vec![King, Ten, Jack, Jack, Ten], 220),
-            (
-                Hand::ThreeOfAKind,
-                vec![Queen, Queen, Queen, Jack, Ace],
-                483,
-            ),
+            (Hand::from_signature(&[2, 1, 1, 1]), cards("32T3K"), 765),
+            (Hand::from_signature(&[3, 1, 1]), cards("T55J5"), 684),
+            (Hand::from_signature(&[2, 2, 1]), cards("KK677"), 28),
+            (Hand::from_signature(&[2, 2, 1]), cards("KTJJT"), 220),
+            (Hand::from_signature(&[3, 1, 1]), cards("QQQJA"), 483),
         ];
         assert_eq!(expected, parsed);
     }
 
     #[test]
     fn test_card() {
-        for (string, expected) in [("T", Card::Ten), ("A", Card::Ace), ("2", Card::Two)] {
-            if let Ok(c) = string.parse::<Card>() {
-                assert_eq!(c, expected);
-            } else {
-                panic!()
-            }
-        }
+        let rules = Rules::standard();
+        assert!(rules.card('T').unwrap() < rules.card('J').unwrap());
+        assert!(rules.card('A').unwrap() > rules.card('K').unwrap());
+        assert!(rules.card('2').unwrap() < rules.card('3').unwrap());
+        assert!(!rules.card('J').unwrap().is_wild());
+        assert_eq!(rules.card('X'), Err("Invalid card: X".to_string()));
+    }
+
+    #[test]
+    fn test_wild_card() {
+        let rules = Rules::jokers();
+        assert!(rules.card('J').unwrap().is_wild());
+        assert!(rules.card('J').unwrap() < rules.card('2').unwrap());
+        let rules = Rules::new("23456789TJQKA", Some('J'), WildRank::Highest);
+        assert!(rules.card('J').unwrap() > rules.card('A').unwrap());
+        let rules = Rules::new("23456789TJQKA", Some('J'), WildRank::Natural);
+        assert!(rules.card('J').unwrap() < rules.card('Q').unwrap());
+    }
+
+    #[test]
+    fn test_hand_wild() {
+        let rules = Rules::jokers();
+        let hand = |labels| Hand::new(&rules.cards(labels).unwrap());
+        assert_eq!(hand("32T3K"), Hand::from_signature(&[2, 1, 1, 1]));
+        assert_eq!(hand("KTJJT"), Hand::from_signature(&[4, 1]));
+        assert_eq!(hand("QQQJA"), Hand::from_signature(&[4, 1]));
+        assert_eq!(hand("2345J"), Hand::from_signature(&[2, 1, 1, 1]));
+        assert_eq!(hand("22J33"), Hand::from_signature(&[3, 2]));
+        assert_eq!(hand("JJJJJ"), Hand::from_signature(&[5]));
     }
 
     #[test]
     fn test_hand_high_card() {
-        let cards = vec![Card::Ace, Card::Two, Card::Three, Card::Four, Card::Five];
-        let hand = Hand::new(cards);
-        assert_eq!(hand, Hand::HighCard);
+        let cards = cards("A2345");
+        let hand = Hand::new(&cards);
+        assert_eq!(hand, Hand::from_signature(&[1, 1, 1, 1, 1]));
     }
     #[test]
     fn test_hand_pair() {
-        let cards = vec![Card::Ace, Card::Two, Card::Three, Card::Ace, Card::Five];
-        let hand = Hand::new(cards);
-        assert_eq!(dbg!(hand), Hand::Pair);
+        let cards = cards("A23A5");
+        let hand = Hand::new(&cards);
+        assert_eq!(dbg!(hand), Hand::from_signature(&[2, 1, 1, 1]));
     }
 
     #[test]
     fn test_hand_two_pair() {
-        let cards = vec![Card::Ace, Card::Three, Card::Three, Card::Ace, Card::Five];
-        let hand = Hand::new(cards);
-        assert_eq!(dbg!(hand), Hand::TwoPair);
+        let cards = cards("A33A5");
+        let hand = Hand::new(&cards);
+        assert_eq!(dbg!(hand), Hand::from_signature(&[2, 2, 1]));
     }
     #[test]
     fn test_hand_three_of_a_kind() {
-        let cards = vec![Card::Ace, Card::Ace, Card::Three, Card::Ace, Card::Five];
-        let hand = Hand::new(cards);
-        assert_eq!(dbg!(hand), Hand::ThreeOfAKind);
+        let cards = cards("AA3A5");
+        let hand = Hand::new(&cards);
+        assert_eq!(dbg!(hand), Hand::from_signature(&[3, 1, 1]));
     }
     #[test]
     fn test_hand_full() {
-        let cards = vec![Card::Ace, Card::Ace, Card::Three, Card::Ace, Card::Three];
-        let hand = Hand::new(cards);
-        assert_eq!(dbg!(hand), Hand::FullHouse);
+        let cards = cards("AA3A3");
+        let hand = Hand::new(&cards);
+        assert_eq!(dbg!(hand), Hand::from_signature(&[3, 2]));
     }
     #[test]
     fn test_hand_four() {
-        let cards = vec![Card::Ace, Card::Ace, Card::Ace, Card::Ace, Card::Three];
-        let hand = Hand::new(cards);
-        assert_eq!(dbg!(hand), Hand::FourOfAKind);
+        let cards = cards("AAAA3");
+        let hand = Hand::new(&cards);
+        assert_eq!(dbg!(hand), Hand::from_signature(&[4, 1]));
     }
     #[test]
     fn test_hand_five() {
-        let cards = vec![Card::Ace, Card::Ace, Card::Ace, Card::Ace, Card::Ace];
-        let hand = Hand::new(cards);
-        assert_eq!(dbg!(hand), Hand::FiveOfAKind);
+        let cards = cards("AAAAA");
+        let hand = Hand::new(&cards);
+        assert_eq!(dbg!(hand), Hand::from_signature(&[5]));
+    }
+
+    #[test]
+    fn test_signature() {
+        let rules = Rules::jokers();
+        let signature = |labels| Signature::new(&rules.cards(labels).unwrap());
+        assert_eq!(signature("KTJJT"), Signature(vec![4, 1]));
+        assert_eq!(signature("23"), Signature(vec![1, 1]));
+        assert_eq!(signature("JJ2233"), Signature(vec![4, 2]));
+        assert_eq!(signature("AAAAAAA"), Signature(vec![7]));
+        assert_eq!(signature(""), Signature(vec![]));
+        assert!(signature("AAA") > signature("AA2"));
+        assert!(signature("2233") > signature("2345"));
+        assert!(signature("22334") < signature("22233"));
+    }
+
+    #[test]
+    fn test_classifier_flush() {
+        let rules = Rules::standard();
+        let classifier = Classifier::default().with_category(Category {
+            name: "flush".to_string(),
+            above: Signature(vec![3, 1, 1]),
+            matches: is_flush,
+        });
+        let hand = |cards| classifier.classify(&rules.suited_cards(cards).unwrap());
+        let flush = hand("2h5h9hThKh");
+        assert_eq!(flush.category(), Some(0));
+        assert_eq!(flush.signature(), &Signature(vec![1, 1, 1, 1, 1]));
+        assert!(flush > hand("2h2s2d5hKh"));
+        assert!(flush < hand("2h2s2d5h5s"));
+        // a full house flush stays a full house
+        let full_flush = hand("2h2h2h5h5h");
+        assert_eq!(full_flush.category(), None);
+        assert_eq!(full_flush, hand("2h2s2d5h5s"));
+        assert_eq!(hand("2h5s9hThKh").category(), None);
+        assert!(!is_flush(&rules.cards("23456").unwrap()));
+        assert!(rules.suited_cards("2h3").is_err());
     }
 }
@@ -1,4 +1,6 @@
-use num::integer::lcm;
+use itertools::Itertools;
+use num::integer::{lcm, ExtendedGcd, Integer};
+use regex::Regex;
 use rustc_hash::FxHashMap as HashMap;
 use winnow::ascii::line_ending;
 use winnow::combinator::{alt, delimited, preceded, repeat, terminated};
@@ -40,46 +42,260 @@ fn step(input: &mut &str) -> PResult<(String, (String, String))> {
     Ok((start.to_string(), (left.to_string(), right.to_string())))
 }
 
-pub fn parse_input(input: &str) -> (Vec<Dir>, HashMap<String, (String, String)>) {
+/// How to pick start or end nodes by name
+#[derive(Debug, Clone)]
+pub enum NodeMatcher {
+    Exact(String),
+    Suffix(String),
+    Regex(Regex),
+}
+
+impl NodeMatcher {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NodeMatcher::Exact(n) => name == n,
+            NodeMatcher::Suffix(s) => name.ends_with(s.as_str()),
+            NodeMatcher::Regex(re) => re.is_match(name),
+        }
+    }
+}
+
+/// Nodes visited from the start to the first end node, both included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    pub steps: usize,
+    pub path: Vec<u32>,
+}
+
+/// Network with node names interned to ids, successors being stored flat:
+/// left of node `id` at `2 * id`, right at `2 * id + 1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+    successors: Vec<u32>,
+}
+
+impl Network {
+    pub fn new(steps: Vec<(String, (String, String))>) -> Result<Self, String> {
+        let names = steps.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>();
+        let mut ids = HashMap::default();
+        for (i, name) in names.iter().enumerate() {
+            if ids.insert(name.clone(), i as u32).is_some() {
+                return Err(format!("Duplicate node: {name}"));
+            }
+        }
+        let id = |name: &String| {
+            ids.get(name)
+                .copied()
+                .ok_or_else(|| format!("Unknown node: {name}"))
+        };
+        let successors = steps
+            .iter()
+            .flat_map(|(_, (left, right))| [id(left), id(right)])
+            .collect::<Result<_, _>>()?;
+        Ok(Network {
+            names,
+            ids,
+            successors,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: u32) -> &str {
+        &self.names[id as usize]
+    }
+
+    pub fn next(&self, id: u32, dir: Dir) -> u32 {
+        match dir {
+            Dir::Left => self.successors[2 * id as usize],
+            Dir::Right => self.successors[2 * id as usize + 1],
+        }
+    }
+
+    /// Left and right nodes of `name`
+    pub fn successors(&self, name: &str) -> Option<(&str, &str)> {
+        let id = self.id(name)?;
+        Some((
+            self.name(self.next(id, Dir::Left)),
+            self.name(self.next(id, Dir::Right)),
+        ))
+    }
+
+    /// Ids of the nodes whose name matches, in input order
+    pub fn matching(&self, matcher: &NodeMatcher) -> Vec<u32> {
+        (0..self.len() as u32)
+            .filter(|&id| matcher.matches(self.name(id)))
+            .collect()
+    }
+
+    fn mask(&self, matcher: &NodeMatcher) -> Vec<bool> {
+        self.names.iter().map(|n| matcher.matches(n)).collect()
+    }
+
+    /// Walk from `start` until an end node, at least one step. None if no end
+    /// node is reachable.
+    pub fn walk(&self, directions: &[Dir], start: u32, end: &NodeMatcher) -> Option<Walk> {
+        let is_end = self.mask(end);
+        // after visiting every (node, instruction) state, the walk loops
+        let max_steps = self.len() * directions.len();
+        let mut path = vec![start];
+        let mut pos = start;
+        for (steps, dir) in (1..=max_steps).zip(directions.iter().cycle()) {
+            pos = self.next(pos, *dir);
+            path.push(pos);
+            if is_end[pos as usize] {
+                return Some(Walk { steps, path });
+            }
+        }
+        None
+    }
+
+    pub fn ghost_cycle(&self, directions: &[Dir], start: u32, end: &NodeMatcher) -> Cycle {
+        let is_end = self.mask(end);
+        let n = directions.len();
+        // step at which each (node, instruction index) state was first seen
+        let mut seen = vec![usize::MAX; self.len() * n];
+        let mut hits = vec![];
+        let mut pos = start;
+        let mut step = 0;
+        loop {
+            let i = step % n;
+            let state = pos as usize * n + i;
+            if seen[state] != usize::MAX {
+                return Cycle {
+                    prefix: seen[state],
+                    length: step - seen[state],
+                    hits,
+                };
+            }
+            if is_end[pos as usize] {
+                hits.push(step);
+            }
+            seen[state] = step;
+            pos = self.next(pos, directions[i]);
+            step += 1;
+        }
+    }
+}
+
+pub fn parse_input(input: &str) -> (Vec<Dir>, Network) {
     let mut input = input;
     let dirs = terminated(directions, (line_ending, line_ending))
         .parse_next(&mut input)
         .unwrap();
     let steps: Vec<(String, (String, String))> = repeat(1.., step).parse_next(&mut input).unwrap();
-    (dirs, HashMap::from_iter(steps))
+    (dirs, Network::new(steps).unwrap())
 }
 
-pub fn part1((directions, steps): (Vec<Dir>, HashMap<String, (String, String)>)) -> usize {
-    solve(&directions, &steps, "AAA")
+pub fn part1((directions, network): (Vec<Dir>, Network)) -> usize {
+    let start = network.id("AAA").expect("no AAA node");
+    network
+        .walk(&directions, start, &NodeMatcher::Exact("ZZZ".to_string()))
+        .expect("ZZZ is not reachable")
+        .steps
 }
 
-fn solve(directions: &[Dir], steps: &HashMap<String, (String, String)>, start: &str) -> usize {
-    let mut cycle = directions.iter().cycle();
-    let mut pos = start.to_string();
-    let mut num_steps = 0;
-    while !pos.ends_with('Z') {
-        let possible = &steps[&pos];
-        pos = match cycle.next() {
-            Some(Dir::Left) => possible.0.to_string(),
-            Some(Dir::Right) => possible.1.to_string(),
-            _ => panic!("weird direction"),
-        };
-        num_steps += 1
+/// The walk of a ghost is eventually periodic on (node, instruction index)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// steps before entering the cycle
+    pub prefix: usize,
+    /// length of the cycle
+    pub length: usize,
+    /// steps before `prefix + length` where the ghost is on an end node
+    pub hits: Vec<usize>,
+}
+
+impl Cycle {
+    fn is_hit(&self, step: usize) -> bool {
+        if step < self.prefix + self.length {
+            self.hits.contains(&step)
+        } else {
+            let step = self.prefix + (step - self.prefix) % self.length;
+            self.hits.contains(&step)
+        }
+    }
+
+    /// Hits that repeat every `length` steps
+    fn periodic_hits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits.iter().copied().filter(|h| *h >= self.prefix)
     }
-    num_steps
 }
 
-fn all_exits(directions: &[Dir], steps: &HashMap<String, (String, String)>) -> Vec<usize> {
-    steps
-        .keys()
-        .filter(|k| k.ends_with('A'))
-        .map(|node| solve(directions, steps, node))
-        .collect()
+/// x = a mod m for both congruences, as (x mod lcm, lcm)
+fn crt((a1, m1): (i128, i128), (a2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    let ExtendedGcd { gcd, x, .. } = m1.extended_gcd(&m2);
+    if (a2 - a1) % gcd != 0 {
+        return None;
+    }
+    let modulus = m1 / gcd * m2;
+    // m1 * x = gcd mod m2
+    let k = ((a2 - a1) / gcd * x).rem_euclid(m2 / gcd);
+    Some(((a1 + m1 * k).rem_euclid(modulus), modulus))
 }
 
-pub fn part2((directions, steps): (Vec<Dir>, HashMap<String, (String, String)>)) -> usize {
-    let exits = all_exits(&directions, &steps);
-    exits.into_iter().reduce(lcm).unwrap()
+/// Every cycle has a single hit, at a multiple of its length: the ghosts
+/// meet after the lcm of the lengths
+fn clean_lcm(cycles: &[Cycle]) -> Option<usize> {
+    let clean = cycles.iter().all(|c| match c.hits[..] {
+        [hit] => hit >= c.prefix && hit % c.length == 0,
+        _ => false,
+    });
+    let meet = cycles.iter().map(|c| c.length).reduce(lcm)?;
+    (clean && cycles.iter().all(|c| c.hits[0] <= meet)).then_some(meet)
+}
+
+/// First step where all the ghosts are on an end node, None if they never are
+pub fn meeting_step(cycles: &[Cycle]) -> Option<usize> {
+    if let Some(meet) = clean_lcm(cycles) {
+        return Some(meet);
+    }
+    // a hit before a ghost enters its cycle only happens once
+    let transient = cycles
+        .iter()
+        .flat_map(|c| c.hits.iter().copied())
+        .filter(|&h| cycles.iter().all(|c| c.is_hit(h)))
+        .min();
+    // otherwise, pick one periodic hit per ghost and solve the congruences
+    let periodic = cycles
+        .iter()
+        .map(|c| c.periodic_hits().collect::<Vec<_>>())
+        .multi_cartesian_product()
+        .filter_map(|hits| {
+            let (x, modulus) = hits
+                .iter()
+                .zip(cycles)
+                .map(|(&h, c)| (h as i128, c.length as i128))
+                .try_fold((0, 1), crt)?;
+            // the smallest solution after every ghost entered its cycle
+            let min = *hits.iter().max()? as i128;
+            let x = x + Integer::div_ceil(&(min - x).max(0), &modulus) * modulus;
+            usize::try_from(x).ok()
+        })
+        .min();
+    transient.into_iter().chain(periodic).min()
+}
+
+pub fn part2((directions, network): (Vec<Dir>, Network)) -> usize {
+    let end = NodeMatcher::Suffix("Z".to_string());
+    let cycles = network
+        .matching(&NodeMatcher::Suffix("A".to_string()))
+        .into_iter()
+        .map(|start| network.ghost_cycle(&directions, start, &end))
+        .collect::<Vec<_>>();
+    meeting_step(&cycles).expect("ghosts never meet on end nodes")
 }
 
 #[cfg(test)]
@@ -89,7 +305,7 @@ mod test {
     use pretty_assertions::assert_eq;
 
     #[test]
-    fn test_all_exits() {
+    fn test_ghost_cycle() {
         let input = indoc! {
         "LR
 
@@ -103,10 +319,58 @@ mod test {
         XXX = (XXX, XXX)
         "
         };
-        let (directions, steps) = parse_input(input);
-        let mut all = all_exits(&directions, &steps);
-        all.sort();
-        assert_eq!(all, vec![2, 3]);
+        let (directions, network) = parse_input(input);
+        let end = NodeMatcher::Suffix("Z".to_string());
+        let ghost_cycle = |name| network.ghost_cycle(&directions, network.id(name).unwrap(), &end);
+        assert_eq!(
+            ghost_cycle("11A"),
+            Cycle {
+                prefix: 1,
+                length: 2,
+                hits: vec![2]
+            }
+        );
+        assert_eq!(
+            ghost_cycle("22A"),
+            Cycle {
+                prefix: 1,
+                length: 6,
+                hits: vec![3, 6]
+            }
+        );
+    }
+
+    #[test]
+    fn test_meeting_step() {
+        let cycle = |prefix, length, hits: &[usize]| Cycle {
+            prefix,
+            length,
+            hits: hits.to_vec(),
+        };
+        // clean lcm
+        assert_eq!(
+            meeting_step(&[cycle(1, 4, &[4]), cycle(2, 6, &[6])]),
+            Some(12)
+        );
+        // offsets different from the lengths: 3 mod 4 and 5 mod 6
+        assert_eq!(
+            meeting_step(&[cycle(0, 4, &[3]), cycle(0, 6, &[5])]),
+            Some(11)
+        );
+        // incompatible offsets
+        assert_eq!(meeting_step(&[cycle(0, 4, &[1]), cycle(0, 6, &[2])]), None);
+        // meeting before the second ghost enters its cycle
+        assert_eq!(
+            meeting_step(&[cycle(0, 1, &[0]), cycle(5, 3, &[2, 6])]),
+            Some(2)
+        );
+        // solution has to be after all the ghosts are in their cycle
+        assert_eq!(
+            meeting_step(&[cycle(0, 2, &[1]), cycle(9, 3, &[10])]),
+            Some(13)
+        );
+        // no end node reachable
+        assert_eq!(meeting_step(&[cycle(0, 2, &[1]), cycle(0, 3, &[])]), None);
     }
 
     #[test]
@@ -177,19 +441,57 @@ mod test {
             ZZZ = (ZZZ, ZZZ)
             "
         };
-        let (dirs, steps) = parse_input(input);
+        let (dirs, network) = parse_input(input);
         assert_eq!(dirs, vec![Dir::Right, Dir::Left]);
-        assert_eq!(
-            steps,
-            HashMap::from_iter(vec![
-                ("AAA".to_string(), ("BBB".to_string(), "CCC".to_string())),
-                ("BBB".to_string(), ("DDD".to_string(), "EEE".to_string())),
-                ("CCC".to_string(), ("ZZZ".to_string(), "GGG".to_string())),
-                ("DDD".to_string(), ("DDD".to_string(), "DDD".to_string())),
-                ("EEE".to_string(), ("EEE".to_string(), "EEE".to_string())),
-                ("GGG".to_string(), ("GGG".to_string(), "GGG".to_string())),
-                ("ZZZ".to_string(), ("ZZZ".to_string(), "ZZZ".to_string())),
-            ])
-        )
+        assert_eq!(network.len(), 7);
+        assert_eq!(network.successors("AAA"), Some(("BBB", "CCC")));
+        assert_eq!(network.successors("BBB"), Some(("DDD", "EEE")));
+        assert_eq!(network.successors("ZZZ"), Some(("ZZZ", "ZZZ")));
+        assert_eq!(network.successors("XXX"), None);
+        assert_eq!(network.next(network.id("CCC").unwrap(), Dir::Left), 6);
+    }
+
+    #[test]
+    fn test_network_unknown_node() {
+        let steps = vec![("AAA".to_string(), ("BBB".to_string(), "AAA".to_string()))];
+        assert_eq!(Network::new(steps), Err("Unknown node: BBB".to_string()));
+    }
+
+    #[test]
+    fn test_network_duplicate_node() {
+        let steps = vec![
+            ("AAA".to_string(), ("AAA".to_string(), "AAA".to_string())),
+            ("AAA".to_string(), ("AAA".to_string(), "AAA".to_string())),
+        ];
+        assert_eq!(Network::new(steps), Err("Duplicate node: AAA".to_string()));
+    }
+
+    #[test]
+    fn test_walk() {
+        let input = indoc! {
+            "LLR
+
+            AAA = (BBB, BBB)
+            BBB = (AAA, ZZZ)
+            ZZZ = (ZZZ, ZZZ)
+            "
+        };
+        let (directions, network) = parse_input(input);
+        let walk = network
+            .walk(&directions, 0, &NodeMatcher::Exact("ZZZ".to_string()))
+            .unwrap();
+        assert_eq!(walk.steps, 6);
+        let path = walk
+            .path
+            .iter()
+            .map(|id| network.name(*id))
+            .collect::<Vec<_>>();
+        assert_eq!(path, ["AAA", "BBB", "AAA", "BBB", "AAA", "BBB", "ZZZ"]);
+        let re = NodeMatcher::Regex(Regex::new("^B+$").unwrap());
+        assert_eq!(network.matching(&re), vec![1]);
+        assert_eq!(network.walk(&directions, 0, &re).unwrap().steps, 1);
+        // never reached
+        let end = NodeMatcher::Suffix("Q".to_string());
+        assert_eq!(network.walk(&directions, 0, &end), None);
     }
 }
@@ -1,5 +1,7 @@
 use crate::parsers::neg_num;
 use itertools::Itertools;
+use num::BigInt;
+use std::fmt;
 use winnow::ascii::line_ending;
 use winnow::combinator::{repeat, separated, terminated};
 use winnow::{PResult, Parser};
@@ -15,31 +17,88 @@ fn line(input: &mut &str) -> PResult<Vec<isize>> {
     separated(1.., neg_num::<isize>, ' ').parse_next(input)
 }
 
-fn compute_line(nums: &[isize]) -> isize {
-    let mut nums: Vec<isize> = Vec::from(nums);
-    let mut last = *nums.last().unwrap();
-    while !nums.iter().all(|&x| x == 0) {
-        nums = nums
-            .into_iter()
-            .tuple_windows()
-            .map(|(x, y)| y - x)
-            .collect();
-        last += *nums.last().unwrap();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FitError {
+    Empty,
+    /// The last difference row still has a non zero value
+    NeverZero,
+}
+
+impl fmt::Display for FitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FitError::Empty => write!(f, "No values to fit"),
+            FitError::NeverZero => write!(f, "The differences never reach an all zero row"),
+        }
     }
-    last
 }
 
-pub fn part1(input: Vec<Vec<isize>>) -> isize {
-    input.iter().map(|x| compute_line(x)).sum()
+impl std::error::Error for FitError {}
+
+/// Polynomial through a sequence, as its Newton forward differences at
+/// index 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Newton {
+    differences: Vec<i128>,
 }
 
-pub fn part2(input: Vec<Vec<isize>>) -> isize {
-    part1(
-        input
-            .into_iter()
-            .map(|x| x.into_iter().rev().collect())
-            .collect(),
-    )
+impl Newton {
+    pub fn fit(values: &[isize]) -> Result<Self, FitError> {
+        let mut row = values.iter().map(|&x| x as i128).collect::<Vec<_>>();
+        let mut differences = Vec::new();
+        while row.iter().any(|&x| x != 0) {
+            if row.len() == 1 {
+                return Err(FitError::NeverZero);
+            }
+            differences.push(row[0]);
+            row = row
+                .into_iter()
+                .tuple_windows()
+                .map(|(x, y)| y - x)
+                .collect();
+        }
+        if values.is_empty() {
+            return Err(FitError::Empty);
+        }
+        Ok(Newton { differences })
+    }
+
+    /// Degree of the polynomial, None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.differences.len().checked_sub(1)
+    }
+
+    /// Value at any index, 0 being the first value of the sequence
+    pub fn at(&self, index: i128) -> BigInt {
+        // f(k) = sum_j C(k, j) Δ^j f(0), C(k, j) being an integer even when
+        // k is negative
+        let mut binomial = BigInt::from(1);
+        let mut res = BigInt::from(0);
+        for (j, &d) in self.differences.iter().enumerate() {
+            res += &binomial * d;
+            binomial = binomial * (index - j as i128) / (j as i128 + 1);
+        }
+        res
+    }
+}
+
+fn extrapolate(input: &[Vec<isize>], index: impl Fn(&[isize]) -> i128) -> BigInt {
+    input
+        .iter()
+        .map(|x| {
+            Newton::fit(x)
+                .unwrap_or_else(|e| panic!("cannot extrapolate {x:?}: {e}"))
+                .at(index(x))
+        })
+        .sum()
+}
+
+pub fn part1(input: Vec<Vec<isize>>) -> BigInt {
+    extrapolate(&input, |x| x.len() as i128)
+}
+
+pub fn part2(input: Vec<Vec<isize>>) -> BigInt {
+    extrapolate(&input, |_| -1)
 }
 
 #[cfg(test)]
@@ -49,10 +108,51 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     #[test]
-    fn test_compute_line() {
-        assert_eq!(compute_line(&[0, 3, 6, 9, 12, 15]), 18);
-        assert_eq!(compute_line(&[1, 3, 6, 10, 15, 21]), 28);
-        assert_eq!(compute_line(&[10, 13, 16, 21, 30, 45]), 68);
+    fn test_newton() {
+        let next = |x: &[isize]| Newton::fit(x).unwrap().at(x.len() as i128);
+        assert_eq!(next(&[0, 3, 6, 9, 12, 15]), BigInt::from(18));
+        assert_eq!(next(&[1, 3, 6, 10, 15, 21]), BigInt::from(28));
+        assert_eq!(next(&[10, 13, 16, 21, 30, 45]), BigInt::from(68));
+        let newton = Newton::fit(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(newton.degree(), Some(3));
+        assert_eq!(newton.at(-1), BigInt::from(5));
+        assert_eq!(newton.at(2), BigInt::from(16));
+        assert_eq!(Newton::fit(&[0, 0]).unwrap().degree(), None);
+        assert_eq!(Newton::fit(&[7, 7]).unwrap().at(-100), BigInt::from(7));
+    }
+
+    #[test]
+    fn test_newton_far() {
+        // n^2 - 3
+        let squares = (0..5).map(|n| n * n - 3).collect::<Vec<_>>();
+        let newton = Newton::fit(&squares).unwrap();
+        assert_eq!(newton.at(-7), BigInt::from(46));
+        let far = 10_i128.pow(20);
+        assert_eq!(newton.at(far), BigInt::from(far) * far - 3);
+        // values overflowing isize in the difference rows
+        let big = Newton::fit(&[isize::MIN + 1, 0, isize::MAX]).unwrap();
+        assert_eq!(big.at(3), BigInt::from(isize::MAX) * 2);
+        assert_eq!(big.at(-1), BigInt::from(isize::MAX) * -2);
+    }
+
+    #[test]
+    fn test_newton_errors() {
+        assert_eq!(Newton::fit(&[]), Err(FitError::Empty));
+        assert_eq!(Newton::fit(&[1, 2, 4, 8, 16]), Err(FitError::NeverZero));
+        // a single value cannot tell anything about its differences
+        assert_eq!(Newton::fit(&[7]), Err(FitError::NeverZero));
+        assert_eq!(Newton::fit(&[1, 2]), Err(FitError::NeverZero));
+    }
+
+    #[test]
+    fn test_parts() {
+        let input = indoc! {
+        "0 3 6 9 12 15
+        1 3 6 10 15 21
+        10 13 16 21 30 45
+        "};
+        assert_eq!(part1(parse_input(input)), BigInt::from(114));
+        assert_eq!(part2(parse_input(input)), BigInt::from(2));
     }
 
     #[test]
@@ -1,5 +1,6 @@
+use crate::geometry::interior_points;
 use num::complex::Complex;
-use rustc_hash::FxHashMap as HashMap;
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
 type Coord = Complex<isize>;
 const LEFT: Coord = Complex::new(-1, 0);
 const RIGHT: Coord = Complex::new(1, 0);
@@ -8,38 +9,269 @@ const UP: Coord = Complex::new(0, -1);
 const DOWN: Coord = Complex::new(0, 1);
 type State = HashMap<Coord, Coord>;
 
-pub fn parse_input(input: &str) -> (Coord, HashMap<Coord, State>) {
-    let mut m = HashMap::default();
-    let mut start = Complex::new(-1, -1);
-    for (i, line) in input.lines().enumerate() {
-        for (j, c) in line.chars().enumerate() {
-            let pos = Complex::new(j as isize, i as isize);
-            match c {
-                '.' => (),
-                'S' => start = pos,
-                '-' => {
-                    m.insert(pos, HashMap::from_iter([(RIGHT, RIGHT), (LEFT, LEFT)]));
-                }
-                '|' => {
-                    m.insert(pos, HashMap::from_iter([(UP, UP), (DOWN, DOWN)]));
-                }
-                'J' => {
-                    m.insert(pos, HashMap::from_iter([(RIGHT, UP), (DOWN, LEFT)]));
-                }
-                '7' => {
-                    m.insert(pos, HashMap::from_iter([(RIGHT, DOWN), (UP, LEFT)]));
-                }
-                'L' => {
-                    m.insert(pos, HashMap::from_iter([(LEFT, UP), (DOWN, RIGHT)]));
+/// The two sides a pipe connects to
+fn pipe_exits(c: char) -> Option<[Coord; 2]> {
+    match c {
+        '-' => Some([LEFT, RIGHT]),
+        '|' => Some([UP, DOWN]),
+        'J' => Some([UP, LEFT]),
+        '7' => Some([DOWN, LEFT]),
+        'L' => Some([UP, RIGHT]),
+        'F' => Some([DOWN, RIGHT]),
+        _ => None,
+    }
+}
+
+/// Entering direction to leaving direction, for a pipe connecting to `exits`
+fn state([a, b]: [Coord; 2]) -> State {
+    HashMap::from_iter([(-a, b), (-b, a)])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Loop,
+    Inside,
+    Outside,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeError {
+    InvalidChar {
+        pos: Coord,
+        c: char,
+    },
+    NoStart,
+    MultipleStarts(Coord, Coord),
+    /// fewer than 2 neighbours connect to the start
+    BrokenStart {
+        connections: usize,
+    },
+    /// more than 2 neighbours connect to the start
+    AmbiguousStart {
+        connections: usize,
+    },
+    /// the loop stops at this tile without coming back to the start
+    BrokenLoop(Coord),
+}
+
+impl std::fmt::Display for MazeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MazeError::InvalidChar { pos, c } => {
+                write!(f, "invalid character {c:?} at ({}, {})", pos.re, pos.im)
+            }
+            MazeError::NoStart => write!(f, "no start tile"),
+            MazeError::MultipleStarts(a, b) => write!(
+                f,
+                "several start tiles, at ({}, {}) and ({}, {})",
+                a.re, a.im, b.re, b.im
+            ),
+            MazeError::BrokenStart { connections } => {
+                write!(f, "start connects to {connections} pipes, expected 2")
+            }
+            MazeError::AmbiguousStart { connections } => write!(
+                f,
+                "start connects to {connections} pipes, cannot infer its shape"
+            ),
+            MazeError::BrokenLoop(pos) => {
+                write!(f, "loop is broken at ({}, {})", pos.re, pos.im)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MazeError {}
+
+/// Pipe grid, with the pipe under `S` inferred and the main loop extracted
+#[derive(Debug, Clone)]
+pub struct PipeMaze {
+    /// tiles with `S` replaced by its pipe
+    grid: Vec<Vec<char>>,
+    start: Coord,
+    transitions: HashMap<Coord, State>,
+    /// the main loop, in order from the start
+    main_loop: Vec<Coord>,
+    on_loop: HashSet<Coord>,
+}
+
+impl std::str::FromStr for PipeMaze {
+    type Err = MazeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut grid = s
+            .lines()
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let mut transitions = HashMap::default();
+        let mut start = None;
+        for (i, line) in grid.iter().enumerate() {
+            for (j, &c) in line.iter().enumerate() {
+                let pos = Complex::new(j as isize, i as isize);
+                match (c, pipe_exits(c)) {
+                    (_, Some(exits)) => {
+                        transitions.insert(pos, state(exits));
+                    }
+                    ('.', _) => (),
+                    ('S', _) => match start {
+                        None => start = Some(pos),
+                        Some(first) => return Err(MazeError::MultipleStarts(first, pos)),
+                    },
+                    (c, _) => return Err(MazeError::InvalidChar { pos, c }),
                 }
-                'F' => {
-                    m.insert(pos, HashMap::from_iter([(UP, RIGHT), (LEFT, DOWN)]));
+            }
+        }
+        let start = start.ok_or(MazeError::NoStart)?;
+
+        // the start is connected to the neighbours that accept a move from it
+        let exits = [UP, RIGHT, DOWN, LEFT]
+            .into_iter()
+            .filter(|d| next(start, *d, &transitions).is_some())
+            .collect::<Vec<_>>();
+        let exits: [Coord; 2] = match exits.len() {
+            0 | 1 => Err(MazeError::BrokenStart {
+                connections: exits.len(),
+            }),
+            2 => Ok([exits[0], exits[1]]),
+            connections => Err(MazeError::AmbiguousStart { connections }),
+        }?;
+        let pipe = "-|J7LF"
+            .chars()
+            .find(|c| pipe_exits(*c).is_some_and(|e| e == exits || e == [exits[1], exits[0]]))
+            .unwrap();
+        grid[start.im as usize][start.re as usize] = pipe;
+        transitions.insert(start, state(exits));
+
+        let mut main_loop = vec![start];
+        let (mut pos, mut dir) = (start, exits[0]);
+        loop {
+            (pos, dir) = next(pos, dir, &transitions).ok_or(MazeError::BrokenLoop(pos))?;
+            if pos == start {
+                break;
+            }
+            main_loop.push(pos);
+        }
+        let on_loop = main_loop.iter().copied().collect();
+        Ok(PipeMaze {
+            grid,
+            start,
+            transitions,
+            main_loop,
+            on_loop,
+        })
+    }
+}
+
+impl PipeMaze {
+    pub fn start(&self) -> Coord {
+        self.start
+    }
+
+    /// The pipe under `S`
+    pub fn start_pipe(&self) -> char {
+        self.grid[self.start.im as usize][self.start.re as usize]
+    }
+
+    pub fn main_loop(&self) -> &[Coord] {
+        &self.main_loop
+    }
+
+    /// The character at `pos`, `S` being replaced by its pipe
+    pub fn pipe(&self, pos: Coord) -> Option<char> {
+        if pos.re < 0 || pos.im < 0 {
+            return None;
+        }
+        self.grid
+            .get(pos.im as usize)
+            .and_then(|l| l.get(pos.re as usize))
+            .copied()
+    }
+
+    /// Every tile of the grid, row by row, as loop, inside or outside
+    pub fn tiles(&self) -> Vec<Vec<Tile>> {
+        self.grid
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                // crossing a pipe going up toggles inside and outside
+                let mut inside = false;
+                (0..line.len())
+                    .map(|j| {
+                        let pos = Complex::new(j as isize, i as isize);
+                        if self.on_loop.contains(&pos) {
+                            if self.transitions[&pos].contains_key(&DOWN) {
+                                inside = !inside;
+                            }
+                            Tile::Loop
+                        } else if inside {
+                            Tile::Inside
+                        } else {
+                            Tile::Outside
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const LOOP_STYLE: &str = "\x1b[1;32m";
+const INSIDE_STYLE: &str = "\x1b[1;33m";
+const JUNK_INSIDE_STYLE: &str = "\x1b[2;33m";
+const JUNK_STYLE: &str = "\x1b[2m";
+
+fn box_drawing(c: char) -> char {
+    match c {
+        'F' => '┌',
+        '7' => '┐',
+        'J' => '┘',
+        'L' => '└',
+        '|' => '│',
+        '-' => '─',
+        c => c,
+    }
+}
+
+impl PipeMaze {
+    /// The main loop drawn with box characters, enclosed tiles as `I` and the
+    /// rest as `.`. With `ansi`, junk pipes are shown dimmed and the loop and
+    /// enclosed tiles highlighted.
+    pub fn render(&self, ansi: bool) -> String {
+        let mut out = String::new();
+        for (line, tiles) in self.grid.iter().zip(self.tiles()) {
+            for (&c, tile) in line.iter().zip(tiles) {
+                let junk = c != '.';
+                let (style, glyph) = match tile {
+                    Tile::Loop => (LOOP_STYLE, box_drawing(c)),
+                    Tile::Inside if ansi && junk => (JUNK_INSIDE_STYLE, box_drawing(c)),
+                    Tile::Inside => (INSIDE_STYLE, 'I'),
+                    Tile::Outside if ansi && junk => (JUNK_STYLE, box_drawing(c)),
+                    Tile::Outside => ("", '.'),
+                };
+                if ansi && !style.is_empty() {
+                    out.push_str(style);
+                    out.push(glyph);
+                    out.push_str(RESET);
+                } else {
+                    out.push(glyph);
                 }
-                _ => panic!("Invalid character: {}", c),
-            };
+            }
+            out.push('\n');
         }
+        out
+    }
+}
+
+impl std::fmt::Display for PipeMaze {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(false))
     }
-    (start, m)
+}
+
+pub fn parse_input(input: &str) -> PipeMaze {
+    input
+        .parse()
+        .unwrap_or_else(|e| panic!("invalid maze: {e}"))
 }
 
 fn next(pos: Coord, dir: Coord, transitions: &HashMap<Coord, State>) -> Option<(Coord, Coord)> {
@@ -49,41 +281,12 @@ fn next(pos: Coord, dir: Coord, transitions: &HashMap<Coord, State>) -> Option<(
     }
 }
 
-pub fn part1((start, transitions): (Coord, HashMap<Coord, State>)) -> usize {
-    let mut len = 1;
-    let (mut pos, mut dir) = [LEFT, RIGHT, UP, DOWN]
-        .iter()
-        .find_map(|d| next(start, *d, &transitions))
-        .unwrap();
-    // start is not in the graph, so we made a round when we get None
-    // I do not know why I cannot assign pos and dir directly
-    while let Some(x) = next(pos, dir, &transitions) {
-        (pos, dir) = x;
-        len += 1;
-    }
-    len / 2 + 1
-}
-
-pub fn part2((start, transitions): (Coord, HashMap<Coord, State>)) -> isize {
-    let mut graph = vec![start];
-    let (mut pos, mut dir) = [LEFT, RIGHT, UP, DOWN]
-        .iter()
-        .filter_map(|d| next(start, *d, &transitions))
-        .next()
-        .unwrap();
-    // shoelace formula https://en.wikipedia.org/wiki/Shoelace_formula
-    // A &= \frac 1 2 \sum_{i=1}^n (y_i + y_{i+1})(x_i - x_{i+1})\\
-    let mut prev = start;
-    let mut area = 0;
-    let mut length = 1;
-    while let Some(x) = next(pos, dir, &transitions) {
-        (pos, dir) = x;
-        graph.push(pos);
-        area += (pos.im + prev.im) * (prev.re - pos.re);
-        length += 1;
-        prev = pos;
-    }
-    (area.abs() - length) / 2 + ((area.abs() - length) % 2).signum()
+pub fn part1(maze: PipeMaze) -> usize {
+    maze.main_loop().len() / 2
+}
+
+pub fn part2(maze: PipeMaze) -> isize {
+    interior_points(maze.main_loop())
 }
 
 #[cfg(test)]
@@ -104,8 +307,7 @@ mod tests {
         .|..|.|..|.
         .L--J.L--J.
         ..........."};
-        let (start, m) = parse_input(input);
-        assert_eq!(part2((start, m)), 4);
+        assert_eq!(part2(parse_input(input)), 4);
     }
     #[test]
     fn test_part2_simple2() {
@@ -119,8 +321,7 @@ mod tests {
         .|..||..|.
         .L--JL--J.
         .........."};
-        let (start, m) = parse_input(input);
-        assert_eq!(part2((start, m)), 4);
+        assert_eq!(part2(parse_input(input)), 4);
     }
     #[test]
     fn test_part2_complex() {
@@ -135,8 +336,7 @@ mod tests {
         .....|FJLJ|FJ|F7|.LJ
         ....FJL-7.||.||||...
         ....L---J.LJ.LJLJ..."};
-        let (start, m) = parse_input(input);
-        assert_eq!(part2((start, m)), 8);
+        assert_eq!(part2(parse_input(input)), 8);
     }
 
     #[test]
@@ -147,8 +347,7 @@ mod tests {
          L|7||
          -L-J|
          L|-JF"};
-        let (start, m) = parse_input(input);
-        assert_eq!(part1((start, m)), 4);
+        assert_eq!(part1(parse_input(input)), 4);
     }
 
     #[test]
@@ -160,8 +359,7 @@ mod tests {
         |F--J
         LJ.LJ
         "};
-        let (start, m) = parse_input(input);
-        assert_eq!(part1((start, m)), 8);
+        assert_eq!(part1(parse_input(input)), 8);
     }
     #[test]
     fn test_next() {
@@ -171,9 +369,12 @@ mod tests {
         .|.|.
         .L-J.
         ....."};
-        let (start, m) = parse_input(input);
-        assert_eq!(next(start, UP, &m), None);
-        assert_eq!(next(start, DOWN, &m), Some((Complex::new(1, 2), DOWN)));
+        let maze = parse_input(input);
+        assert_eq!(next(maze.start, UP, &maze.transitions), None);
+        assert_eq!(
+            next(maze.start, DOWN, &maze.transitions),
+            Some((Complex::new(1, 2), DOWN))
+        );
     }
 
     #[test]
@@ -184,11 +385,155 @@ mod tests {
         .|.|.
         .L-J.
         ....."};
-        let (start, m) = parse_input(input);
-        assert_eq!(start, Complex::new(1, 1));
+        let maze = parse_input(input);
+        assert_eq!(maze.start(), Complex::new(1, 1));
+        assert_eq!(maze.start_pipe(), 'F');
         assert_eq!(
-            m[&Complex::new(2, 1)],
+            maze.transitions[&Complex::new(2, 1)],
             HashMap::from_iter([(RIGHT, RIGHT), (LEFT, LEFT)])
         );
     }
+
+    #[test]
+    fn test_main_loop() {
+        let input = indoc! {
+        "-L|F7
+        7S-7|
+        L|7||
+        -L-J|
+        L|-JF"};
+        let maze = parse_input(input);
+        assert_eq!(
+            maze.main_loop(),
+            [
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (3, 2),
+                (3, 3),
+                (2, 3),
+                (1, 3),
+                (1, 2)
+            ]
+            .map(|(x, y)| Complex::new(x, y))
+        );
+    }
+
+    #[test]
+    fn test_tiles() {
+        let input = indoc! {
+        ".F----7F7F7F7F-7....
+        .|F--7||||||||FJ....
+        .||.FJ||||||||L7....
+        FJL7L7LJLJ||LJ.L-7..
+        L--J.L7...LJS7F-7L7.
+        ....F-J..F7FJ|L7L7L7
+        ....L7.F7||L7|.L7L7|
+        .....|FJLJ|FJ|F7|.LJ
+        ....FJL-7.||.||||...
+        ....L---J.LJ.LJLJ..."};
+        let maze = parse_input(input);
+        assert_eq!(maze.start_pipe(), 'F');
+        let tiles = maze.tiles().into_iter().flatten().collect::<Vec<_>>();
+        let count = |t| tiles.iter().filter(|x| **x == t).count();
+        assert_eq!(count(Tile::Inside), 8);
+        assert_eq!(count(Tile::Loop), maze.main_loop().len());
+        assert_eq!(count(Tile::Outside), 200 - 8 - maze.main_loop().len());
+    }
+
+    #[test]
+    fn test_maze_errors() {
+        use std::str::FromStr;
+        assert_eq!(PipeMaze::from_str(".-.\n").unwrap_err(), MazeError::NoStart);
+        assert_eq!(
+            PipeMaze::from_str("S.S\n").unwrap_err(),
+            MazeError::MultipleStarts(Complex::new(0, 0), Complex::new(2, 0))
+        );
+        assert_eq!(
+            PipeMaze::from_str("S-x\n").unwrap_err(),
+            MazeError::InvalidChar {
+                pos: Complex::new(2, 0),
+                c: 'x'
+            }
+        );
+        assert_eq!(
+            PipeMaze::from_str(".S-\n").unwrap_err(),
+            MazeError::BrokenStart { connections: 1 }
+        );
+        assert_eq!(
+            PipeMaze::from_str(".|.\n-S-\n.|.").unwrap_err(),
+            MazeError::AmbiguousStart { connections: 4 }
+        );
+        assert_eq!(
+            PipeMaze::from_str(".|.\n-S.\n...").unwrap_err(),
+            MazeError::BrokenLoop(Complex::new(1, 0))
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let input = indoc! {
+        "...........
+        .S-------7.
+        .|F-----7|.
+        .||.....||.
+        .||.....||.
+        .|L-7.F-J|.
+        .|..|.|..|.
+        .L--J.L--J.
+        ..........."};
+        let expected = indoc! {
+        "...........
+        .┌───────┐.
+        .│┌─────┐│.
+        .││.....││.
+        .││.....││.
+        .│└─┐.┌─┘│.
+        .│II│.│II│.
+        .└──┘.└──┘.
+        ...........
+        "};
+        assert_eq!(parse_input(input).to_string(), expected);
+    }
+
+    #[test]
+    fn test_render_complex() {
+        let input = indoc! {
+        ".F----7F7F7F7F-7....
+        .|F--7||||||||FJ....
+        .||.FJ||||||||L7....
+        FJL7L7LJLJ||LJ.L-7..
+        L--J.L7...LJS7F-7L7.
+        ....F-J..F7FJ|L7L7L7
+        ....L7.F7||L7|.L7L7|
+        .....|FJLJ|FJ|F7|.LJ
+        ....FJL-7.||.||||...
+        ....L---J.LJ.LJLJ..."};
+        let expected = indoc! {
+        ".┌────┐┌┐┌┐┌┐┌─┐....
+        .│┌──┐││││││││┌┘....
+        .││.┌┘││││││││└┐....
+        ┌┘└┐└┐└┘└┘││└┘I└─┐..
+        └──┘.└┐III└┘┌┐┌─┐└┐.
+        ....┌─┘II┌┐┌┘│└┐└┐└┐
+        ....└┐I┌┐││└┐│I└┐└┐│
+        .....│┌┘└┘│┌┘│┌┐│.└┘
+        ....┌┘└─┐.││.││││...
+        ....└───┘.└┘.└┘└┘...
+        "};
+        assert_eq!(parse_input(input).render(false), expected);
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let maze = parse_input("7-F\nFS7\nL-J\n");
+        let expected = [
+            "\x1b[2m┐\x1b[0m\x1b[2m─\x1b[0m\x1b[2m┌\x1b[0m",
+            "\x1b[1;32m┌\x1b[0m\x1b[1;32m─\x1b[0m\x1b[1;32m┐\x1b[0m",
+            "\x1b[1;32m└\x1b[0m\x1b[1;32m─\x1b[0m\x1b[1;32m┘\x1b[0m",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(maze.render(true), expected);
+    }
 }
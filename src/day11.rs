@@ -1,31 +1,182 @@
-use itertools::Itertools;
 use num::complex::Complex;
 
 type Coord = Complex<isize>;
 
-fn expand_galaxies(galaxies: Vec<Coord>, offset: isize) -> Vec<Coord> {
-    let h_len = galaxies.iter().map(|c| c.re).max().unwrap();
-    let v_len = galaxies.iter().map(|c| c.im).max().unwrap();
-    let empty_cols = (0..h_len)
-        .filter(|i| !galaxies.iter().any(|g| g.re == *i))
-        .collect::<Vec<_>>();
-    let empty_rows = (0..v_len)
-        .filter(|i| !galaxies.iter().any(|g| g.im == *i))
-        .collect::<Vec<_>>();
-    //expand
+/// How many columns and rows each empty column and row becomes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expansion {
+    pub cols: isize,
+    pub rows: isize,
+}
+
+impl Expansion {
+    pub fn new(cols: isize, rows: isize) -> Self {
+        Expansion { cols, rows }
+    }
+
+    pub fn uniform(factor: isize) -> Self {
+        Expansion::new(factor, factor)
+    }
+}
+
+/// `empty[i]` is the number of coordinates in `0..i` with no galaxy
+fn empty_before(coords: impl Iterator<Item = isize>) -> Vec<isize> {
+    let coords = coords.collect::<Vec<_>>();
+    let len = coords.iter().max().map_or(0, |m| m + 1) as usize;
+    let mut occupied = vec![false; len];
+    coords.into_iter().for_each(|c| occupied[c as usize] = true);
+    let mut empty = vec![0; len + 1];
+    for (i, o) in occupied.into_iter().enumerate() {
+        empty[i + 1] = empty[i] + isize::from(!o);
+    }
+    empty
+}
+
+pub fn expand_galaxies(galaxies: Vec<Coord>, expansion: Expansion) -> Vec<Coord> {
+    let empty_cols = empty_before(galaxies.iter().map(|c| c.re));
+    let empty_rows = empty_before(galaxies.iter().map(|c| c.im));
     galaxies
         .into_iter()
         .map(|c| {
-            let h_offset = empty_cols.iter().filter(|&i| *i < c.re).count() as isize;
-            let v_offset = empty_rows.iter().filter(|&j| *j < c.im).count() as isize;
             Complex::new(
-                c.re + h_offset * (offset - 1),
-                c.im + v_offset * (offset - 1),
+                c.re + empty_cols[c.re as usize] * (expansion.cols - 1),
+                c.im + empty_rows[c.im as usize] * (expansion.rows - 1),
             )
         })
         .collect()
 }
 
+/// Sum of `|a - b|` over all pairs, `values` being sorted
+fn pairwise_sum(values: impl ExactSizeIterator<Item = isize>) -> isize {
+    let n = values.len() as isize;
+    values
+        .enumerate()
+        .map(|(k, v)| (2 * k as isize - n + 1) * v)
+        .sum()
+}
+
+/// Pairwise distances along one axis, split between the distance before
+/// expansion and the number of empty lines crossed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AxisDistances {
+    raw: isize,
+    empty: isize,
+}
+
+impl AxisDistances {
+    fn new(coords: impl Iterator<Item = isize>) -> Self {
+        let mut coords = coords.collect::<Vec<_>>();
+        coords.sort_unstable();
+        // empty lines before each coordinate, from the number of distinct
+        // coordinates before it
+        let mut distinct = 0;
+        let empty = coords
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| {
+                if k > 0 && coords[k - 1] != c {
+                    distinct += 1;
+                }
+                c - distinct
+            })
+            .collect::<Vec<_>>();
+        AxisDistances {
+            raw: pairwise_sum(coords.into_iter()),
+            empty: pairwise_sum(empty.into_iter()),
+        }
+    }
+
+    fn total(&self, factor: isize) -> isize {
+        self.raw + self.empty * (factor - 1)
+    }
+}
+
+/// Sum of the Manhattan distances between all pairs of galaxies, for any
+/// expansion, in O(n log n) once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PairwiseDistances {
+    cols: AxisDistances,
+    rows: AxisDistances,
+}
+
+impl PairwiseDistances {
+    pub fn new(galaxies: &[Coord]) -> Self {
+        PairwiseDistances {
+            cols: AxisDistances::new(galaxies.iter().map(|c| c.re)),
+            rows: AxisDistances::new(galaxies.iter().map(|c| c.im)),
+        }
+    }
+
+    pub fn total(&self, expansion: Expansion) -> isize {
+        self.cols.total(expansion.cols) + self.rows.total(expansion.rows)
+    }
+}
+
+/// Expanded galaxies, numbered in reading order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GalaxyIndex {
+    galaxies: Vec<Coord>,
+}
+
+impl GalaxyIndex {
+    pub fn new(galaxies: Vec<Coord>, expansion: Expansion) -> Self {
+        GalaxyIndex {
+            galaxies: expand_galaxies(galaxies, expansion),
+        }
+    }
+
+    pub fn galaxies(&self) -> &[Coord] {
+        &self.galaxies
+    }
+
+    pub fn len(&self) -> usize {
+        self.galaxies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.galaxies.is_empty()
+    }
+
+    /// Manhattan distance between galaxies `i` and `j`, None if out of range
+    pub fn distance(&self, i: usize, j: usize) -> Option<isize> {
+        Some((self.galaxies.get(j)? - self.galaxies.get(i)?).l1_norm())
+    }
+
+    /// The `k` galaxies closest to `point` (in expanded coordinates), as
+    /// `(galaxy, distance)` sorted by distance then galaxy number
+    pub fn nearest(&self, point: Coord, k: usize) -> Vec<(usize, isize)> {
+        let mut distances = self
+            .galaxies
+            .iter()
+            .enumerate()
+            .map(|(i, g)| (i, (g - point).l1_norm()))
+            .collect::<Vec<_>>();
+        distances.sort_unstable_by_key(|&(i, d)| (d, i));
+        distances.truncate(k);
+        distances
+    }
+
+    pub fn distance_matrix(&self) -> Vec<Vec<isize>> {
+        self.galaxies
+            .iter()
+            .map(|a| self.galaxies.iter().map(|b| (b - a).l1_norm()).collect())
+            .collect()
+    }
+
+    /// Distance matrix as CSV, with the galaxy numbers as header and first column
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("galaxy");
+        (0..self.len()).for_each(|i| csv.push_str(&format!(",{i}")));
+        csv.push('\n');
+        for (i, row) in self.distance_matrix().into_iter().enumerate() {
+            csv.push_str(&i.to_string());
+            row.into_iter().for_each(|d| csv.push_str(&format!(",{d}")));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
 pub fn parse_input(input: &str) -> Vec<Coord> {
     let mut galaxies = vec![];
     input.lines().enumerate().for_each(|(j, line)| {
@@ -40,27 +191,18 @@ pub fn parse_input(input: &str) -> Vec<Coord> {
 }
 
 pub fn part1(galaxies: Vec<Coord>) -> isize {
-    let galaxies = expand_galaxies(galaxies, 2);
-    galaxies
-        .into_iter()
-        .combinations(2)
-        .map(|a| (a[1] - a[0]).l1_norm())
-        .sum()
+    PairwiseDistances::new(&galaxies).total(Expansion::uniform(2))
 }
 
 pub fn part2(galaxies: Vec<Coord>) -> isize {
-    let galaxies = expand_galaxies(galaxies, 1_000_000);
-    galaxies
-        .into_iter()
-        .combinations(2)
-        .map(|a| (a[1] - a[0]).l1_norm())
-        .sum()
+    PairwiseDistances::new(&galaxies).total(Expansion::uniform(1_000_000))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use indoc::indoc;
+    use itertools::Itertools;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -111,7 +253,7 @@ mod tests {
         // 8....9.......
         let parsed = parse_input(input);
         assert_eq!(
-            expand_galaxies(parsed, 2),
+            expand_galaxies(parsed, Expansion::uniform(2)),
             vec![
                 Complex::new(4, 0),
                 Complex::new(9, 1),
@@ -125,4 +267,74 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_pairwise_distances() {
+        let input = indoc! {
+        "...#......
+        .......#..
+        #.........
+        ..........
+        ......#...
+        .#........
+        .........#
+        ..........
+        .......#..
+        #...#.....
+        "};
+        let parsed = parse_input(input);
+        let distances = PairwiseDistances::new(&parsed);
+        assert_eq!(distances.total(Expansion::uniform(2)), 374);
+        assert_eq!(distances.total(Expansion::uniform(10)), 1030);
+        assert_eq!(distances.total(Expansion::uniform(100)), 8410);
+        for expansion in [
+            Expansion::new(1, 1),
+            Expansion::new(3, 7),
+            Expansion::new(50, 2),
+        ] {
+            let brute_force: isize = expand_galaxies(parsed.clone(), expansion)
+                .into_iter()
+                .combinations(2)
+                .map(|a| (a[1] - a[0]).l1_norm())
+                .sum();
+            assert_eq!(distances.total(expansion), brute_force);
+        }
+    }
+
+    #[test]
+    fn test_galaxy_index() {
+        let input = indoc! {
+        "...#......
+        .......#..
+        #.........
+        ..........
+        ......#...
+        .#........
+        .........#
+        ..........
+        .......#..
+        #...#.....
+        "};
+        let index = GalaxyIndex::new(parse_input(input), Expansion::uniform(2));
+        assert_eq!(index.len(), 9);
+        assert_eq!(index.distance(4, 8), Some(9));
+        assert_eq!(index.distance(0, 6), Some(15));
+        assert_eq!(index.distance(2, 5), Some(17));
+        assert_eq!(index.distance(7, 8), Some(5));
+        assert_eq!(index.distance(7, 9), None);
+        assert_eq!(
+            index.nearest(Complex::new(0, 11), 3),
+            vec![(7, 0), (8, 5), (4, 6)]
+        );
+        assert_eq!(index.nearest(Complex::new(0, 0), 20).len(), 9);
+        let matrix = index.distance_matrix();
+        assert_eq!(matrix.iter().flatten().sum::<isize>(), 2 * 374);
+        assert_eq!(matrix[8][4], 9);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let index = GalaxyIndex::new(parse_input("#.\n..\n.#\n"), Expansion::uniform(10));
+        assert_eq!(index.to_csv(), "galaxy,0,1\n0,0,12\n1,12,0\n");
+    }
 }
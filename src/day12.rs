@@ -1,27 +1,5 @@
 use itertools::{repeat_n, Itertools};
-use memoize::memoize;
-
-fn check_fit(line: &str, groups: &[usize]) -> bool {
-    let g: Vec<_> = line
-        .split(|c: char| c == '.')
-        .map(|s| s.len())
-        .filter(|s| *s > 0)
-        .collect();
-    g.len() == groups.len() && g.iter().zip(groups).all(|(l, r)| l == r)
-}
-
-fn solve_one(line: &str, groups: &[usize]) -> usize {
-    line.chars()
-        .map(|c| match c {
-            '?' => vec!['.', '#'],
-            x => vec![x],
-        })
-        .multi_cartesian_product()
-        .map(|x| x.iter().collect::<String>())
-        .map(|x| check_fit(&x, groups))
-        .filter(|x| *x)
-        .count()
-}
+use rand::Rng;
 
 pub fn parse_input(input: &str) -> Vec<(String, Vec<usize>)> {
     input
@@ -34,124 +12,193 @@ pub fn parse_input(input: &str) -> Vec<(String, Vec<usize>)> {
         .collect()
 }
 
-pub fn part1(input: Vec<(String, Vec<usize>)>) -> usize {
-    input
-        .into_iter()
-        .map(|(line, groups)| solve_one(&line, &groups))
-        .sum()
+/// Repeat the springs `factor` times joined by `?`, and the groups `factor` times
+pub fn unfold(springs: &str, groups: &[usize], factor: usize) -> (String, Vec<usize>) {
+    (repeat_n(springs, factor).join("?"), groups.repeat(factor))
 }
 
-pub fn part1_recursive(input: Vec<(String, Vec<usize>)>) -> usize {
-    input
-        .into_iter()
-        .map(|(line, groups)| solve_recursive(line.to_string(), groups))
-        .sum()
+/// Arrangements of a row, backed by a table where `ways[i * (groups.len() + 1) + g]`
+/// is the number of arrangements of `springs[i..]` matching `groups[g..]`
+pub struct Arrangements {
+    springs: Vec<u8>,
+    groups: Vec<usize>,
+    // operational[i] is the number of '.' in springs[..i]
+    operational: Vec<usize>,
+    ways: Vec<usize>,
 }
 
-#[memoize]
-fn solve_recursive(string: String, target: Vec<usize>) -> usize {
-    let string = string.trim_start_matches('.');
-    if target.is_empty() {
-        return match string.contains('#') {
-            true => 0,
-            false => 1,
+impl Arrangements {
+    pub fn new(springs: &[u8], groups: &[usize]) -> Self {
+        let n = springs.len();
+        let mut operational = vec![0; n + 1];
+        for (i, c) in springs.iter().enumerate() {
+            operational[i + 1] = operational[i] + usize::from(*c == b'.');
+        }
+        let mut arrangements = Arrangements {
+            springs: springs.to_vec(),
+            groups: groups.to_vec(),
+            operational,
+            ways: vec![0; (n + 1) * (groups.len() + 1)],
         };
+        arrangements.fill();
+        arrangements
+    }
+
+    fn index(&self, i: usize, g: usize) -> usize {
+        i * (self.groups.len() + 1) + g
+    }
+
+    fn fill(&mut self) {
+        let n = self.springs.len();
+        let end = self.index(n, self.groups.len());
+        self.ways[end] = 1;
+        for i in (0..n).rev() {
+            for g in 0..=self.groups.len() {
+                let count = self.count_operational(i, g) + self.count_damaged(i, g);
+                let idx = self.index(i, g);
+                self.ways[idx] = count;
+            }
+        }
+    }
+
+    /// Position after `groups[g]` placed at `i` and its separator, if it fits
+    fn group_end(&self, i: usize, g: usize) -> Option<usize> {
+        if self.springs[i] == b'.' || g == self.groups.len() {
+            return None;
+        }
+        let end = i + self.groups[g];
+        (end <= self.springs.len()
+            && self.operational[end] == self.operational[i]
+            && self.springs.get(end) != Some(&b'#'))
+        .then_some((end + 1).min(self.springs.len()))
     }
 
-    // println!("{sep}string: {}, target {:?}", string, target);
-    if !string.contains('?') {
-        // nothing to replace
-        let splits = string
-            .split('.')
-            .filter(|x| !x.is_empty())
-            .collect::<Vec<_>>();
-        match target.len() == splits.len()
-            && target.iter().zip(splits.iter()).all(|(t, s)| s.len() == *t)
-        {
-            true => 1,
-            false => 0,
+    /// Arrangements of `springs[i..]` with spring `i` operational
+    fn count_operational(&self, i: usize, g: usize) -> usize {
+        match self.springs[i] {
+            b'#' => 0,
+            _ => self.ways[self.index(i + 1, g)],
         }
-    } else {
-        if string.len() < target[0] {
-            return 0;
+    }
+
+    /// Arrangements of `springs[i..]` with group `g` starting at spring `i`
+    fn count_damaged(&self, i: usize, g: usize) -> usize {
+        self.group_end(i, g)
+            .map_or(0, |next| self.ways[self.index(next, g + 1)])
+    }
+
+    pub fn count(&self) -> usize {
+        self.ways[0]
+    }
+
+    /// The `k`-th arrangement (0 based) in lexicographic order, `#` being
+    /// before `.`
+    pub fn nth(&self, mut k: usize) -> Option<String> {
+        if k >= self.count() {
+            return None;
         }
-        let mut max_start = string.find('#').unwrap_or(string.len() - target[0]) + 1;
-        max_start = max_start.min(string.len() - target[0] + 1);
-
-        let mut res = 0;
-        for start in 0..max_start {
-            res += if string[start..start + target[0]].chars().all(|c| c != '.') {
-                match string.chars().nth(start + target[0]) {
-                    Some('#') => 0,
-                    Some(_) => solve_recursive(
-                        string[start + target[0] + 1..].to_string(),
-                        target.clone().into_iter().skip(1).collect(),
-                    ),
-                    None => match target.len() {
-                        1 => 1,
-                        _ => 0,
-                    },
+        let n = self.springs.len();
+        let mut row = String::with_capacity(n);
+        let (mut i, mut g) = (0, 0);
+        while i < n {
+            let damaged = self.count_damaged(i, g);
+            if k < damaged {
+                let next = self.group_end(i, g).unwrap();
+                row.push_str(&"#".repeat(self.groups[g]));
+                if next > i + self.groups[g] {
+                    row.push('.');
                 }
+                (i, g) = (next, g + 1);
             } else {
-                0
-            };
+                k -= damaged;
+                row.push('.');
+                i += 1;
+            }
+        }
+        Some(row)
+    }
+
+    /// All the arrangements in lexicographic order, computed on demand
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.count()).map(|k| self.nth(k).unwrap())
+    }
+
+    /// An arrangement drawn uniformly, None if there is none
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Option<String> {
+        match self.count() {
+            0 => None,
+            count => self.nth(rng.gen_range(0..count)),
         }
-        res
     }
 }
 
-pub fn part2(input: Vec<(String, Vec<usize>)>) -> usize {
+/// Number of ways to replace the `?` so that the damaged springs form `groups`
+pub fn count_arrangements(springs: &[u8], groups: &[usize]) -> usize {
+    Arrangements::new(springs, groups).count()
+}
+
+/// Tries every assignment of the `?`, only to check `count_arrangements` on
+/// short rows. Reference implementation standing in for the former
+/// `solve_one`/`check_fit` brute force and `part1_recursive`.
+pub fn count_brute_force(springs: &[u8], groups: &[usize]) -> usize {
+    let unknown = springs.iter().positions(|&c| c == b'?').collect_vec();
+    (0..1_usize << unknown.len())
+        .filter(|mask| {
+            let mut row = springs.to_vec();
+            for (k, &i) in unknown.iter().enumerate() {
+                row[i] = if (mask >> k) & 1 == 1 { b'#' } else { b'.' };
+            }
+            row.split(|&c| c == b'.')
+                .filter(|g| !g.is_empty())
+                .map(<[u8]>::len)
+                .eq(groups.iter().copied())
+        })
+        .count()
+}
+
+/// Sum of the arrangements of every row once unfolded `factor` times
+pub fn solve(input: &[(String, Vec<usize>)], factor: usize) -> usize {
     input
-        .into_iter()
-        .map(|(line, groups)| {
-            (
-                [line.clone(), line.clone(), line.clone(), line.clone(), line].join("?"),
-                repeat_n(groups, 5).flatten().collect::<Vec<_>>(),
-            )
+        .iter()
+        .map(|(springs, groups)| {
+            let (springs, groups) = unfold(springs, groups, factor);
+            count_arrangements(springs.as_bytes(), &groups)
         })
-        .map(|(line, groups)| solve_recursive(line.to_string(), groups))
         .sum()
 }
 
+pub fn part1(input: Vec<(String, Vec<usize>)>) -> usize {
+    solve(&input, 1)
+}
+
+pub fn part2(input: Vec<(String, Vec<usize>)>) -> usize {
+    solve(&input, 5)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use indoc::indoc;
     use pretty_assertions::assert_eq;
+    use rand::{rngs::StdRng, SeedableRng};
     #[test]
-    fn test_check() {
-        assert_eq!(check_fit("..##...###.", &[2, 3]), true);
-        assert_eq!(check_fit("..##...###.", &[2, 4]), false);
-    }
-
-    #[test]
-    fn test_solve_recursive() {
-        let groups = vec![1usize, 1, 3];
-        let line = "???.###";
-        assert_eq!(solve_recursive(line.to_string(), groups.clone()), 1);
-        let line = ".??..??...?##.".to_string();
-        assert_eq!(solve_recursive(line, groups), 4);
-        let groups = vec![2, 1];
-        let line = "?????".to_string();
-        assert_eq!(solve_recursive(line, groups), 3);
-        let groups = vec![3, 2, 1];
-        let line = "?###????????".to_string();
-        assert_eq!(solve_recursive(line, groups), 10);
-    }
-    #[test]
-    fn test_solve_one() {
+    fn test_count_arrangements() {
         let groups = [1usize, 1, 3];
-        let line = "???.###";
-        assert_eq!(solve_one(line, &groups), 1);
-        let line = ".??..??...?##.";
-        assert_eq!(solve_one(line, &groups), 4);
-        let groups = [3, 2, 1];
-        let line = "?###????????";
-        assert_eq!(solve_one(line, &groups), 10);
+        assert_eq!(count_arrangements(b"???.###", &groups), 1);
+        assert_eq!(count_arrangements(b".??..??...?##.", &groups), 4);
+        assert_eq!(count_arrangements(b"?????", &[2, 1]), 3);
+        assert_eq!(count_arrangements(b"?###????????", &[3, 2, 1]), 10);
+        assert_eq!(count_arrangements(b"#.#", &[2]), 0);
+        assert_eq!(count_arrangements(b"...", &[]), 1);
+        assert_eq!(count_arrangements(b"", &[1]), 0);
     }
 
     #[test]
-    fn test_part1() {
+    fn test_count_brute_force() {
+        assert_eq!(count_brute_force(b".??..??...?##.", &[1, 1, 3]), 4);
+        assert_eq!(count_brute_force(b"?###????????", &[3, 2, 1]), 10);
+        assert_eq!(count_brute_force(b"#.#", &[2]), 0);
+        assert_eq!(count_brute_force(b"", &[]), 1);
         let input = indoc! {
             "???.### 1,1,3
             .??..??...?##. 1,1,3
@@ -160,10 +207,61 @@ mod tests {
             ????.######..#####. 1,6,5
             ?###???????? 3,2,1"
         };
-        assert_eq!(part1(parse_input(input)), 21);
+        for (springs, groups) in parse_input(input) {
+            assert_eq!(
+                count_brute_force(springs.as_bytes(), &groups),
+                count_arrangements(springs.as_bytes(), &groups),
+                "{springs}"
+            );
+        }
     }
+
     #[test]
-    fn test_part1_recursive() {
+    fn test_arrangements_iter() {
+        let arrangements = Arrangements::new(b"?###????????", &[3, 2, 1]);
+        let rows = arrangements.iter().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[0], ".###.##.#...");
+        assert_eq!(rows[9], ".###....##.#");
+        assert!(rows.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(Arrangements::new(b"#.#", &[2]).iter().count(), 0);
+    }
+
+    #[test]
+    fn test_arrangements_nth() {
+        let arrangements = Arrangements::new(b"?????", &[2, 1]);
+        assert_eq!(arrangements.nth(0), Some("##.#.".to_string()));
+        assert_eq!(arrangements.nth(1), Some("##..#".to_string()));
+        assert_eq!(arrangements.nth(2), Some(".##.#".to_string()));
+        assert_eq!(arrangements.nth(3), None);
+    }
+
+    #[test]
+    fn test_arrangements_sample() {
+        let arrangements = Arrangements::new(b".??..??...?##.", &[1, 1, 3]);
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..20 {
+            let row = arrangements.sample(&mut rng).unwrap();
+            assert_eq!(count_arrangements(row.as_bytes(), &[1, 1, 3]), 1);
+        }
+        let mut rng = StdRng::seed_from_u64(12);
+        assert!(Arrangements::new(b"#", &[2]).sample(&mut rng).is_none());
+    }
+
+    #[test]
+    fn test_unfold() {
+        assert_eq!(
+            unfold(".#", &[1], 5),
+            (".#?.#?.#?.#?.#".to_string(), vec![1, 1, 1, 1, 1])
+        );
+        assert_eq!(
+            unfold("???.###", &[1, 1, 3], 1),
+            ("???.###".to_string(), vec![1, 1, 3])
+        );
+    }
+
+    #[test]
+    fn test_part1() {
         let input = indoc! {
             "???.### 1,1,3
             .??..??...?##. 1,1,3
@@ -172,9 +270,8 @@ mod tests {
             ????.######..#####. 1,6,5
             ?###???????? 3,2,1"
         };
-        assert_eq!(part1_recursive(parse_input(input)), 21);
+        assert_eq!(part1(parse_input(input)), 21);
     }
-
     #[test]
     fn test_part2() {
         let input = indoc! {
@@ -197,6 +294,16 @@ mod tests {
         //?###???????? 3,2,1 - 506250 arrangements
 
         assert_eq!(part2(parse_input("???.### 1,1,3")), 1);
-        assert_eq!(part2(parse_input("?#?#?#?#?#?#?#? 1,3,1,6")), 16384);
+        assert_eq!(part2(parse_input(".??..??...?##. 1,1,3")), 16384);
+        assert_eq!(part2(parse_input("?#?#?#?#?#?#?#? 1,3,1,6")), 1);
+        assert_eq!(part2(parse_input("?###???????? 3,2,1")), 506250);
+    }
+
+    #[test]
+    fn test_solve_factor() {
+        let input = parse_input("????.#...#... 4,1,1");
+        assert_eq!(solve(&input, 1), 1);
+        assert_eq!(solve(&input, 2), 2);
+        assert_eq!(solve(&input, 5), 16);
     }
 }
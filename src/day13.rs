@@ -1,7 +1,3 @@
-use crate::helpers::transpose;
-
-use itertools::equal;
-
 type Matrix<T> = Vec<Vec<T>>;
 
 pub fn parse_input(input: &str) -> Vec<Matrix<i8>> {
@@ -24,77 +20,106 @@ pub fn parse_input(input: &str) -> Vec<Matrix<i8>> {
         .collect()
 }
 
-fn find_symmetry(m: &Matrix<i8>) -> Option<usize> {
-    let n = m.len();
-    for span in 1..=n / 2 {
-        if equal(m[0..span].iter(), m[span..2 * span].iter().rev()) {
-            return Some(span);
-        } else if equal(
-            m[n - 2 * span..n - span].iter(),
-            m[n - span..n].iter().rev(),
-        ) {
-            return Some(n - span);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    /// mirror line between two rows
+    Horizontal,
+    /// mirror line between two columns
+    Vertical,
+}
+
+/// A mirror line, `index` is the number of rows (or columns) before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reflection {
+    pub axis: Axis,
+    pub index: usize,
+}
+
+impl Reflection {
+    fn new(axis: Axis, index: usize) -> Self {
+        Reflection { axis, index }
+    }
+
+    /// Puzzle summary: columns to the left, or 100 times the rows above
+    pub fn score(&self) -> usize {
+        match self.axis {
+            Axis::Horizontal => 100 * self.index,
+            Axis::Vertical => self.index,
         }
     }
-    None
 }
 
-pub fn part1(input: Vec<Matrix<i8>>) -> usize {
-    input
-        .into_iter()
-        .map(|m| match find_symmetry(&m) {
-            Some(n) => 100 * n,
-            None => match find_symmetry(&transpose(m)) {
-                Some(n) => n,
-                None => panic!("no symetry found"),
-            },
-        })
-        .sum()
+/// A row or column packed as bits, 64 cells per word
+type Line = Vec<u64>;
+
+/// Rows and columns packed as bits, so comparing two lines is a xor
+struct Pattern {
+    rows: Vec<Line>,
+    cols: Vec<Line>,
 }
 
-pub fn part2(input: Vec<Matrix<i8>>) -> usize {
-    input
-        .into_iter()
-        .map(|m| match find_almost_symmetry(&m) {
-            Some(n) => 100 * n,
-            None => match find_almost_symmetry(&transpose(m)) {
-                Some(n) => n,
-                None => panic!("no symmetry found"),
-            },
-        })
-        .sum()
+impl Pattern {
+    fn new(m: &Matrix<i8>) -> Self {
+        let width = m.first().map_or(0, |r| r.len());
+        let mut rows = vec![vec![0u64; width.div_ceil(64)]; m.len()];
+        let mut cols = vec![vec![0u64; m.len().div_ceil(64)]; width];
+        for (j, line) in m.iter().enumerate() {
+            for (i, &cell) in line.iter().enumerate() {
+                if cell != 0 {
+                    rows[j][i / 64] |= 1 << (i % 64);
+                    cols[i][j / 64] |= 1 << (j % 64);
+                }
+            }
+        }
+        Pattern { rows, cols }
+    }
 }
 
-fn find_almost_symmetry(m: &Matrix<i8>) -> Option<usize> {
-    let n = m.len();
-    for span in 1..=n / 2 {
-        if (m[0..span].iter().zip(m[span..2 * span].iter().rev()))
-            .map(|(v1, v2)| {
-                v1.iter()
-                    .zip(v2.iter())
-                    .map(|(e1, e2)| (e1 - e2).abs())
-                    .sum::<i8>()
-            })
-            .sum::<i8>()
-            == 1
-        {
-            return Some(span);
-        } else if (m[n - 2 * span..n - span]
+/// Number of differing cells when folding `lines` before `index`, stops
+/// counting once `limit` is exceeded
+fn differences(lines: &[Line], index: usize, limit: u32) -> u32 {
+    let mut diff = 0;
+    for (a, b) in lines[..index].iter().rev().zip(&lines[index..]) {
+        diff += a
             .iter()
-            .zip(m[n - span..n].iter().rev()))
-        .map(|(v1, v2)| {
-            v1.iter()
-                .zip(v2.iter())
-                .map(|(e1, e2)| (e1 - e2).abs())
-                .sum::<i8>()
-        })
-        .sum::<i8>()
-            == 1
-        {
-            return Some(n - span);
+            .zip(b)
+            .map(|(x, y)| (x ^ y).count_ones())
+            .sum::<u32>();
+        if diff > limit {
+            break;
         }
     }
-    None
+    diff
+}
+
+fn axis_reflections(lines: &[Line], smudges: u32) -> impl Iterator<Item = usize> + '_ {
+    (1..lines.len()).filter(move |&index| differences(lines, index, smudges) == smudges)
+}
+
+/// All mirror lines, horizontal first, that need exactly `smudges` cells to
+/// be fixed to become a perfect reflection
+pub fn find_reflections(m: &Matrix<i8>, smudges: u32) -> Vec<Reflection> {
+    let pattern = Pattern::new(m);
+    axis_reflections(&pattern.rows, smudges)
+        .map(|i| Reflection::new(Axis::Horizontal, i))
+        .chain(axis_reflections(&pattern.cols, smudges).map(|i| Reflection::new(Axis::Vertical, i)))
+        .collect()
+}
+
+fn summarize(input: &[Matrix<i8>], smudges: u32) -> usize {
+    input
+        .iter()
+        .flat_map(|m| find_reflections(m, smudges))
+        .map(|r| r.score())
+        .sum()
+}
+
+pub fn part1(input: Vec<Matrix<i8>>) -> usize {
+    summarize(&input, 0)
+}
+
+pub fn part2(input: Vec<Matrix<i8>>) -> usize {
+    summarize(&input, 1)
 }
 
 #[cfg(test)]
@@ -127,7 +152,7 @@ mod tests {
     }
 
     #[test]
-    fn test_find_symmetry() {
+    fn test_find_reflections() {
         let input = indoc! {
         "#.##..##.
         ..#.##.#.
@@ -147,8 +172,40 @@ mod tests {
         };
 
         let parsed = parse_input(input);
-        let symmetries = parsed.iter().map(find_symmetry).collect::<Vec<_>>();
-        assert_eq!(symmetries, vec![None, Some(4)]);
+        let reflections = parsed
+            .iter()
+            .map(|m| find_reflections(m, 0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reflections,
+            vec![
+                vec![Reflection::new(Axis::Vertical, 5)],
+                vec![Reflection::new(Axis::Horizontal, 4)]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_reflections_none() {
+        let parsed = parse_input("#..\n.#.\n..#\n");
+        assert_eq!(find_reflections(&parsed[0], 0), vec![]);
+        assert_eq!(part1(parsed), 0);
+    }
+
+    #[test]
+    fn test_find_reflections_large() {
+        let row = (0..130).map(|i| (i * 7 % 3 == 0) as i8).collect::<Vec<_>>();
+        let wide = vec![row.clone(), row.clone()];
+        assert!(find_reflections(&wide, 0).contains(&Reflection::new(Axis::Horizontal, 1)));
+        let mut smudged = row.clone();
+        smudged[100] = 1 - smudged[100];
+        let wide = vec![row, smudged];
+        assert!(!find_reflections(&wide, 0).contains(&Reflection::new(Axis::Horizontal, 1)));
+        assert!(find_reflections(&wide, 1).contains(&Reflection::new(Axis::Horizontal, 1)));
+        let tall = (0..70)
+            .map(|j| vec![(j % 5 == 0) as i8; 2])
+            .collect::<Vec<_>>();
+        assert!(find_reflections(&tall, 0).contains(&Reflection::new(Axis::Vertical, 1)));
     }
 
     #[test]
@@ -176,7 +233,7 @@ mod tests {
     }
 
     #[test]
-    fn test_find_almost_symmetry() {
+    fn test_find_reflections_smudged() {
         let input = indoc! {
         "#.##..##.
         ..#.##.#.
@@ -196,8 +253,17 @@ mod tests {
         };
 
         let parsed = parse_input(input);
-        let symmetries = parsed.iter().map(find_almost_symmetry).collect::<Vec<_>>();
-        assert_eq!(symmetries, vec![Some(3), Some(1)]);
+        let reflections = parsed
+            .iter()
+            .map(|m| find_reflections(m, 1))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reflections,
+            vec![
+                vec![Reflection::new(Axis::Horizontal, 3)],
+                vec![Reflection::new(Axis::Horizontal, 1)]
+            ]
+        );
     }
 
     #[test]
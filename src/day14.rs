@@ -127,10 +127,11 @@ pub fn part2(input: Matrix<char>) -> usize {
         m = cycle(m);
         let s = to_string(&m);
         if let Some((p, _)) = seen.iter().find_position(|&st| st == &s) {
-            let remaining = tot - i;
+            // m is the state after i + 1 cycles
+            let remaining = tot - i - 1;
             let len_cycle = i - p + 1;
             let num_to_do = remaining % len_cycle;
-            for _ in 1..num_to_do {
+            for _ in 0..num_to_do {
                 m = cycle(m);
             }
             return score(m);
@@ -1,66 +1,175 @@
 use crate::parsers::num;
 
+use std::collections::BTreeMap;
+use std::fmt;
 use winnow::{
     ascii::alpha1,
     combinator::{alt, separated_pair, terminated},
     PResult, Parser,
 };
 
-pub fn parse_input(input: &str) -> Vec<Vec<u8>> {
+/// Steps of the initialization sequence, newlines and surrounding spaces
+/// being ignored
+fn steps(input: &str) -> impl Iterator<Item = String> + '_ {
     input
-        .strip_suffix('\n')
-        .unwrap_or(input)
         .split(',')
-        .map(|x| x.as_bytes().to_vec())
-        .collect()
+        .map(|x| x.chars().filter(|c| !c.is_whitespace()).collect())
+}
+
+pub fn parse_input(input: &str) -> Vec<Vec<u8>> {
+    steps(input).map(String::into_bytes).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashParams {
+    multiplier: usize,
+    modulus: usize,
+}
+
+/// Parameters of the puzzle's HASH algorithm
+pub const HASH: HashParams = HashParams {
+    multiplier: 17,
+    modulus: 256,
+};
+
+impl HashParams {
+    /// Rejects a zero modulus, which leaves no bucket to hash into
+    pub fn new(multiplier: usize, modulus: usize) -> Result<Self, String> {
+        if modulus == 0 {
+            return Err("Modulus must be positive".to_string());
+        }
+        Ok(HashParams {
+            multiplier,
+            modulus,
+        })
+    }
+
+    pub fn multiplier(&self) -> usize {
+        self.multiplier
+    }
+
+    pub fn modulus(&self) -> usize {
+        self.modulus
+    }
+
+    /// Computed in u128 so any multiplier and modulus fit without overflow
+    pub fn hash(&self, chars: &[u8]) -> usize {
+        let (multiplier, modulus) = (self.multiplier as u128, self.modulus as u128);
+        chars.iter().fold(0usize, |acc, c| {
+            ((acc as u128 + *c as u128) * multiplier % modulus) as usize
+        })
+    }
+
+    /// How the distinct labels of the instructions spread over the buckets
+    pub fn bucket_stats(&self, instructions: &[Instruction]) -> BucketStats {
+        let mut labels = instructions
+            .iter()
+            .map(|i| i.label.as_str())
+            .collect::<Vec<_>>();
+        labels.sort_unstable();
+        labels.dedup();
+        let mut distribution = BTreeMap::new();
+        for label in &labels {
+            *distribution.entry(self.hash(label.as_bytes())).or_insert(0) += 1;
+        }
+        BucketStats {
+            labels: labels.len(),
+            used: distribution.len(),
+            max_load: distribution.values().copied().max().unwrap_or(0),
+            distribution,
+        }
+    }
 }
 
 fn hash(chars: &[u8]) -> usize {
-    chars
-        .iter()
-        .fold(0usize, |acc, c| ((acc + *c as usize) * 17) % 256)
+    HASH.hash(chars)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketStats {
+    /// number of distinct labels
+    pub labels: usize,
+    /// number of buckets holding at least one label
+    pub used: usize,
+    /// most labels in a single bucket
+    pub max_load: usize,
+    /// number of labels in each used bucket, so that any modulus fits
+    pub distribution: BTreeMap<usize, usize>,
+}
+
+impl BucketStats {
+    /// Labels landing in a bucket already holding another label
+    pub fn collisions(&self) -> usize {
+        self.labels - self.used
+    }
 }
 
 pub fn part1(input: Vec<Vec<u8>>) -> usize {
     input.iter().map(|p| hash(p)).sum()
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Lens {
     label: String,
     focal: u8,
 }
 
 impl Lens {
-    fn new(label: &str, focal: u8) -> Self {
+    pub fn new(label: &str, focal: u8) -> Self {
         Lens {
             label: label.to_string(),
             focal,
         }
     }
 
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn focal(&self) -> u8 {
+        self.focal
+    }
+
     fn change_focal(&mut self, focal: u8) {
         self.focal = focal;
     }
 }
 
-enum Operation {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
     Dash,
     Equal(u8),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Instruction {
     label: String,
     operation: Operation,
 }
 
+impl Instruction {
+    pub fn new(label: &str, operation: Operation) -> Self {
+        Instruction {
+            label: label.to_string(),
+            operation,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+}
+
 impl std::str::FromStr for Instruction {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut s = s;
         alt((parse_dash, parse_equal))
-            .parse_next(&mut s)
+            .parse(s)
             .map_err(|e| e.to_string())
     }
 }
@@ -82,16 +191,16 @@ fn parse_equal(input: &mut &str) -> PResult<Instruction> {
 }
 
 pub fn parse_input_p2(input: &str) -> Vec<Instruction> {
-    input.split(',').map(|x| x.parse().unwrap()).collect()
+    steps(input).map(|x| x.parse().unwrap()).collect()
 }
 
-fn focusing_power(boxes: Vec<Vec<Lens>>) -> usize {
+fn focusing_power(boxes: &[Vec<Lens>]) -> usize {
     boxes
-        .into_iter()
+        .iter()
         .enumerate()
         .map(|(i, v)| {
             (i + 1)
-                * v.into_iter()
+                * v.iter()
                     .enumerate()
                     .map(|(j, l)| (j + 1) * l.focal as usize)
                     .sum::<usize>()
@@ -99,24 +208,83 @@ fn focusing_power(boxes: Vec<Vec<Lens>>) -> usize {
         .sum()
 }
 
-pub fn part2(instructions: Vec<Instruction>) -> usize {
-    let mut boxes: Vec<Vec<Lens>> = vec![vec![]; 256];
-    for instr in instructions {
-        let box_num = hash(instr.label.as_bytes());
+/// The 256 boxes of the HASHMAP procedure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LensBoxes {
+    boxes: Vec<Vec<Lens>>,
+}
+
+impl Default for LensBoxes {
+    fn default() -> Self {
+        LensBoxes {
+            boxes: vec![vec![]; 256],
+        }
+    }
+}
+
+impl LensBoxes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// State after the first `step` instructions
+    pub fn replay(instructions: &[Instruction], step: usize) -> Self {
+        let mut boxes = Self::new();
+        instructions
+            .iter()
+            .take(step)
+            .for_each(|instr| boxes.apply(instr));
+        boxes
+    }
+
+    pub fn apply(&mut self, instr: &Instruction) {
+        let lenses = &mut self.boxes[hash(instr.label.as_bytes())];
         match instr.operation {
             Operation::Dash => {
-                boxes[box_num].retain(|l| l.label != instr.label);
+                lenses.retain(|l| l.label != instr.label);
             }
             Operation::Equal(n) => {
-                if let Some(l) = boxes[box_num].iter_mut().find(|x| x.label == instr.label) {
+                if let Some(l) = lenses.iter_mut().find(|x| x.label == instr.label) {
                     l.change_focal(n)
                 } else {
-                    boxes[box_num].push(Lens::new(&instr.label, n))
+                    lenses.push(Lens::new(&instr.label, n))
                 }
             }
         }
     }
-    focusing_power(boxes)
+
+    pub fn get(&self, box_num: usize) -> &[Lens] {
+        &self.boxes[box_num]
+    }
+
+    pub fn boxes(&self) -> &[Vec<Lens>] {
+        &self.boxes
+    }
+
+    pub fn focusing_power(&self) -> usize {
+        focusing_power(&self.boxes)
+    }
+}
+
+/// Non empty boxes, like `Box 3: [ot 7] [ab 5]`
+impl fmt::Display for LensBoxes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, lenses) in self.boxes.iter().enumerate() {
+            if lenses.is_empty() {
+                continue;
+            }
+            write!(f, "Box {i}:")?;
+            for l in lenses {
+                write!(f, " [{} {}]", l.label, l.focal)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn part2(instructions: Vec<Instruction>) -> usize {
+    LensBoxes::replay(&instructions, instructions.len()).focusing_power()
 }
 
 #[cfg(test)]
@@ -130,11 +298,58 @@ mod tests {
         assert_eq!(hash(chars), 52);
     }
 
+    #[test]
+    fn test_hash_params() {
+        assert_eq!(HASH.hash(b"rn"), 0);
+        assert_eq!(HASH.hash(b"qp"), 1);
+        let params = HashParams::new(31, 1000).unwrap();
+        assert_eq!(params.hash(b"ab"), ((97 * 31) % 1000 + 98) * 31 % 1000);
+        assert_eq!(params.hash(b""), 0);
+        assert_eq!(
+            HashParams::new(17, 0),
+            Err("Modulus must be positive".to_string())
+        );
+        let large = HashParams::new(usize::MAX, usize::MAX - 1).unwrap();
+        assert_eq!(large.hash(b"a"), 97);
+        assert_eq!(large.hash(b"ab"), 97 + 98);
+    }
+
+    #[test]
+    fn test_bucket_stats() {
+        let input = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+        let stats = HASH.bucket_stats(&parse_input_p2(input));
+        assert_eq!(stats.labels, 6);
+        assert_eq!(stats.used, 3);
+        assert_eq!(stats.max_load, 3);
+        assert_eq!(stats.collisions(), 3);
+        assert_eq!(stats.distribution, BTreeMap::from([(0, 2), (1, 1), (3, 3)]));
+        let one_bucket = HashParams::new(17, 1).unwrap();
+        let stats = one_bucket.bucket_stats(&parse_input_p2(input));
+        assert_eq!(stats.distribution, BTreeMap::from([(0, 6)]));
+        assert_eq!(stats.collisions(), 5);
+        let huge = HashParams::new(31, 1 << 40).unwrap();
+        let stats = huge.bucket_stats(&parse_input_p2(input));
+        assert_eq!(stats.used, 6);
+        assert_eq!(stats.distribution.len(), 6);
+    }
+
     #[test]
     fn test_parse_input() {
         let input = "abc,def";
         let expected = vec![vec![b'a', b'b', b'c'], vec![b'd', b'e', b'f']];
         assert_eq!(parse_input(input), expected);
+        assert_eq!(parse_input("abc,de\nf\n"), expected);
+    }
+
+    #[test]
+    fn test_parse_input_p2() {
+        let expected = vec![
+            Instruction::new("rn", Operation::Equal(1)),
+            Instruction::new("cm", Operation::Dash),
+        ];
+        assert_eq!(parse_input_p2("rn=1,cm-"), expected);
+        assert_eq!(parse_input_p2("rn=1,\ncm-\n"), expected);
+        assert!("rn=1x".parse::<Instruction>().is_err());
     }
 
     #[test]
@@ -151,7 +366,7 @@ mod tests {
             vec![],
             vec![Lens::new("ot", 7), Lens::new("ab", 5), Lens::new("pc", 6)],
         ];
-        assert_eq!(focusing_power(boxes), 145);
+        assert_eq!(focusing_power(&boxes), 145);
     }
 
     #[test]
@@ -160,4 +375,46 @@ mod tests {
 
         assert_eq!(part2(parse_input_p2(input)), 145);
     }
+
+    #[test]
+    fn test_lens_boxes() {
+        let input = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+        let instructions = parse_input_p2(input);
+        assert_eq!(LensBoxes::replay(&instructions, 0), LensBoxes::new());
+        assert_eq!(
+            LensBoxes::replay(&instructions, 1).to_string(),
+            "Box 0: [rn 1]\n"
+        );
+        assert_eq!(
+            LensBoxes::replay(&instructions, 5).to_string(),
+            "Box 0: [rn 1] [cm 2]\n"
+        );
+        let mut boxes = LensBoxes::replay(&instructions, 8);
+        assert_eq!(
+            boxes.to_string(),
+            "Box 0: [rn 1] [cm 2]\nBox 3: [pc 4] [ot 9] [ab 5]\n"
+        );
+        for instr in &instructions[8..] {
+            boxes.apply(instr);
+        }
+        assert_eq!(boxes, LensBoxes::replay(&instructions, 100));
+        assert_eq!(
+            boxes.get(3),
+            &[Lens::new("ot", 7), Lens::new("ab", 5), Lens::new("pc", 6)]
+        );
+        assert_eq!(
+            boxes.to_string(),
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n"
+        );
+        assert_eq!(boxes.focusing_power(), 145);
+    }
+
+    #[test]
+    fn test_instruction() {
+        let instr: Instruction = "cm-".parse().unwrap();
+        assert_eq!(instr, Instruction::new("cm", Operation::Dash));
+        let instr: Instruction = "ot=9".parse().unwrap();
+        assert_eq!(instr.label(), "ot");
+        assert_eq!(instr.operation(), Operation::Equal(9));
+    }
 }
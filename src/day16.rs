@@ -124,27 +124,27 @@ fn explore(
 pub fn part2((grid, bottom_right): (HashMap<Coord, Move>, Coord)) -> usize {
     let mut max = 0;
     // all entries from the top
-    for i in 0..bottom_right.re {
+    for i in 0..=bottom_right.re {
         let start = Complex::new(i, 0);
         let starting_dir = Direction::Down;
         max = explore(&grid, start, starting_dir, bottom_right).max(max);
     }
     // all entries from the bottom
-    for i in 0..bottom_right.re {
+    for i in 0..=bottom_right.re {
         let start = Complex::new(i, bottom_right.im);
         let starting_dir = Direction::Up;
         max = explore(&grid, start, starting_dir, bottom_right).max(max);
     }
     // all entries from the left
-    for j in 0..bottom_right.im {
+    for j in 0..=bottom_right.im {
         let start = Complex::new(0, j);
         let starting_dir = Direction::Right;
         max = explore(&grid, start, starting_dir, bottom_right).max(max);
     }
     // all entries from the right
-    for j in 0..bottom_right.im {
+    for j in 0..=bottom_right.im {
         let start = Complex::new(bottom_right.re, j);
-        let starting_dir = Direction::Up;
+        let starting_dir = Direction::Left;
         max = explore(&grid, start, starting_dir, bottom_right).max(max);
     }
     max
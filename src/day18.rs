@@ -1,3 +1,4 @@
+use crate::geometry::{boundary_points, interior_points, shoelace_area};
 use crate::helpers::Direction;
 use num::Complex;
 
@@ -12,6 +13,41 @@ impl Instr {
     fn new(dir: Direction, val: isize, color: String) -> Self {
         Instr { dir, val, color }
     }
+
+    pub fn as_move(&self) -> (Direction, i128) {
+        (self.dir, self.val as i128)
+    }
+
+    /// Move encoded in the colour: 5 hex digits of length, then the direction
+    pub fn color_move(&self) -> Result<(Direction, i128), String> {
+        //0 means R, 1 means D, 2 means L, and 3 means U
+        let mut chars = self.color.chars();
+        let dir = match chars.next_back() {
+            None => return Err(String::from("no color")),
+            Some('0') => Direction::Right,
+            Some('1') => Direction::Down,
+            Some('2') => Direction::Left,
+            Some('3') => Direction::Up,
+            Some(c) => return Err(format!("Not a correct last char {c}")),
+        };
+        let len = chars.as_str();
+        let len = i128::from_str_radix(len, 16).map_err(|e| format!("{len}: {e}"))?;
+        Ok((dir, len))
+    }
+
+    /// The colour as RGB, to draw the trench edge
+    pub fn rgb(&self) -> Result<Rgb, String> {
+        if self.color.len() != 6 {
+            return Err(format!("Not a RGB color: {}", self.color));
+        }
+        let channel = |i: usize| {
+            self.color
+                .get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("Not a RGB color: {}", self.color))
+        };
+        Ok([channel(0)?, channel(2)?, channel(4)?])
+    }
 }
 
 impl std::str::FromStr for Instr {
@@ -47,45 +83,280 @@ pub fn parse_input(input: &str) -> Vec<Instr> {
     input.lines().map(|line| line.parse().unwrap()).collect()
 }
 
-pub fn part1(input: Vec<Instr>) -> isize {
-    // shoelace formula https://en.wikipedia.org/wiki/Shoelace_formula
-    // A &= \frac 1 2 \sum_{i=1}^n (y_i + y_{i+1})(x_i - x_{i+1})\\
-    let mut prev = Complex::new(0, 0);
-    let mut area: isize = 0;
-    let mut length = 0;
-    for instr in input {
-        let pos = prev + instr.dir.val() * instr.val;
-        area += (pos.im + prev.im) * (prev.re - pos.re);
-        length += instr.val;
-        prev = pos;
-    }
-    (area.abs() + length) / 2 + 1
-}
-
-pub fn part2(input: Vec<Instr>) -> isize {
-    // shoelace formula https://en.wikipedia.org/wiki/Shoelace_formula
-    // A &= \frac 1 2 \sum_{i=1}^n (y_i + y_{i+1})(x_i - x_{i+1})\\
-    let mut prev = Complex::new(0, 0);
-    let mut area: isize = 0;
-    let mut length = 0;
-    for instr in input {
-        //0 means R, 1 means D, 2 means L, and 3 means U
-        let dir = match instr.color.chars().last() {
-            None => panic!("no color"),
-            Some('0') => Direction::Right,
-            Some('1') => Direction::Down,
-            Some('2') => Direction::Left,
-            Some('3') => Direction::Up,
-            Some(c) => panic!("Not a correct last char {c}"),
-        };
-        let val = isize::from_str_radix(&instr.color[0..(instr.color.len() - 1)], 16).unwrap();
-        let pos = prev + dir.val() * val;
-        //A &= \frac 1 2 \sum_{i=1}^n (x_iy_{i+1}-x_{i+1}y_i)
-        area += (prev.re * pos.im) - (pos.re * prev.im);
-        length += val;
-        prev = pos;
+type Point = Complex<i128>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolygonError {
+    /// a move of zero or negative length
+    InvalidLength { index: usize, len: i128 },
+    /// the last move does not come back to the start
+    NotClosed { end: Point },
+    /// edges `first` and `second` cross or overlap
+    SelfIntersecting { first: usize, second: usize },
+}
+
+impl std::fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolygonError::InvalidLength { index, len } => {
+                write!(f, "move {index} has an invalid length {len}")
+            }
+            PolygonError::NotClosed { end } => {
+                write!(
+                    f,
+                    "trench does not close, it ends at ({}, {})",
+                    end.re, end.im
+                )
+            }
+            PolygonError::SelfIntersecting { first, second } => {
+                write!(f, "trench edges {first} and {second} intersect")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+/// Closed rectilinear polygon dug along a trench, on the lattice
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    /// vertices in digging order, starting (and implicitly ending) at the origin
+    vertices: Vec<Point>,
+}
+
+fn to_point(dir: Direction) -> Point {
+    let val = dir.val();
+    Complex::new(val.re as i128, val.im as i128)
+}
+
+/// Axis aligned segments intersect iff their bounding boxes do
+fn segments_intersect((a1, a2): (Point, Point), (b1, b2): (Point, Point)) -> bool {
+    a1.re.min(a2.re) <= b1.re.max(b2.re)
+        && b1.re.min(b2.re) <= a1.re.max(a2.re)
+        && a1.im.min(a2.im) <= b1.im.max(b2.im)
+        && b1.im.min(b2.im) <= a1.im.max(a2.im)
+}
+
+impl Polygon {
+    pub fn from_moves(
+        moves: impl IntoIterator<Item = (Direction, i128)>,
+    ) -> Result<Self, PolygonError> {
+        let mut vertices = vec![Complex::new(0, 0)];
+        let mut dirs = vec![];
+        for (index, (dir, len)) in moves.into_iter().enumerate() {
+            if len <= 0 {
+                return Err(PolygonError::InvalidLength { index, len });
+            }
+            let prev = vertices[vertices.len() - 1];
+            vertices.push(prev + to_point(dir) * len);
+            dirs.push(to_point(dir));
+        }
+        let end = vertices.pop().unwrap();
+        if end != Complex::new(0, 0) || vertices.len() < 2 {
+            return Err(PolygonError::NotClosed { end });
+        }
+
+        let n = vertices.len();
+        let edge = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+        for i in 0..n {
+            // consecutive edges only share a vertex, unless they go back
+            if dirs[(i + 1) % n] == -dirs[i] {
+                return Err(PolygonError::SelfIntersecting {
+                    first: i,
+                    second: (i + 1) % n,
+                });
+            }
+            for j in i + 2..n {
+                if !(i == 0 && j == n - 1) && segments_intersect(edge(i), edge(j)) {
+                    return Err(PolygonError::SelfIntersecting {
+                        first: i,
+                        second: j,
+                    });
+                }
+            }
+        }
+        Ok(Polygon { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// Enclosed area, measured between the centers of the trench cells
+    pub fn area(&self) -> i128 {
+        shoelace_area(&self.vertices)
+    }
+
+    /// Number of trench cells
+    pub fn boundary(&self) -> i128 {
+        boundary_points(&self.vertices)
+    }
+
+    /// Number of cells strictly inside the trench
+    pub fn interior(&self) -> i128 {
+        interior_points(&self.vertices)
+    }
+
+    /// Cubic meters dug out, trench included
+    pub fn volume(&self) -> i128 {
+        self.interior() + self.boundary()
+    }
+}
+
+pub type Rgb = [u8; 3];
+
+const LAGOON: Rgb = [0x40, 0x40, 0x40];
+const GROUND: Rgb = [0xff, 0xff, 0xff];
+/// Larger images should be drawn as SVG
+pub const MAX_PPM_PIXELS: i128 = 4_000_000;
+
+/// Rendered dig plan, in the format fitting its size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Image {
+    Ppm(String),
+    Svg(String),
+}
+
+/// PPM up to `MAX_PPM_PIXELS`, SVG for larger plans
+pub fn render(polygon: &Polygon, colors: &[Rgb]) -> Result<Image, String> {
+    let (width, height) = size(polygon.vertices());
+    if width * height > MAX_PPM_PIXELS {
+        render_svg(polygon, colors).map(Image::Svg)
+    } else {
+        render_ppm(polygon, colors).map(Image::Ppm)
     }
-    (area.abs() + length) / 2 + 1
+}
+
+/// One colour is needed per edge
+fn check_colors(polygon: &Polygon, colors: &[Rgb]) -> Result<(), String> {
+    let edges = polygon.vertices().len();
+    if colors.len() != edges {
+        return Err(format!("Expected {edges} colors, got {}", colors.len()));
+    }
+    Ok(())
+}
+
+/// Dig plan as SVG, the lagoon filled and each edge stroked with its colour
+pub fn render_svg(polygon: &Polygon, colors: &[Rgb]) -> Result<String, String> {
+    check_colors(polygon, colors)?;
+    let vertices = polygon.vertices();
+    let (min, max) = bounds(vertices);
+    let hex = |[r, g, b]: Rgb| format!("#{r:02x}{g:02x}{b:02x}");
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        min.re,
+        min.im,
+        max.re - min.re + 1,
+        max.im - min.im + 1
+    );
+    let points = vertices
+        .iter()
+        .map(|p| format!("{},{}", p.re, p.im))
+        .collect::<Vec<_>>()
+        .join(" ");
+    svg.push_str(&format!(
+        "<polygon points=\"{points}\" fill=\"{}\"/>\n",
+        hex(LAGOON)
+    ));
+    for (i, color) in colors.iter().enumerate() {
+        let (p, q) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" \
+             stroke-width=\"2\" vector-effect=\"non-scaling-stroke\"/>\n",
+            p.re,
+            p.im,
+            q.re,
+            q.im,
+            hex(*color)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Dig plan as a plain PPM image, one pixel per cubic meter
+pub fn render_ppm(polygon: &Polygon, colors: &[Rgb]) -> Result<String, String> {
+    check_colors(polygon, colors)?;
+    let vertices = polygon.vertices();
+    let (min, max) = bounds(vertices);
+    let (width, height) = size(vertices);
+    if width * height > MAX_PPM_PIXELS {
+        return Err(format!("{width}x{height} is too large for PPM, use SVG"));
+    }
+    let (width, height) = (width as usize, height as usize);
+    let mut pixels = vec![GROUND; width * height];
+    let pixel = |p: Point| (p.im - min.im) as usize * width + (p.re - min.re) as usize;
+    let n = vertices.len();
+    // the lagoon, by scanlines crossing the vertical edges
+    for y in min.im..=max.im {
+        let mut crossings = (0..n)
+            .map(|i| (vertices[i], vertices[(i + 1) % n]))
+            .filter(|(p, q)| p.re == q.re && p.im.min(q.im) <= y && y < p.im.max(q.im))
+            .map(|(p, _)| p.re)
+            .collect::<Vec<_>>();
+        crossings.sort_unstable();
+        for pair in crossings.chunks_exact(2) {
+            for x in pair[0]..=pair[1] {
+                pixels[pixel(Complex::new(x, y))] = LAGOON;
+            }
+        }
+    }
+    // then the trench on top of it
+    for (i, color) in colors.iter().enumerate() {
+        let (p, q) = (vertices[i], vertices[(i + 1) % n]);
+        let step = Complex::new((q.re - p.re).signum(), (q.im - p.im).signum());
+        let mut pos = p;
+        while pos != q {
+            pixels[pixel(pos)] = *color;
+            pos += step;
+        }
+    }
+    let mut ppm = format!("P3\n{width} {height}\n255\n");
+    for row in pixels.chunks(width) {
+        let line = row
+            .iter()
+            .map(|[r, g, b]| format!("{r} {g} {b}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        ppm.push_str(&line);
+        ppm.push('\n');
+    }
+    Ok(ppm)
+}
+
+/// Width and height in pixels
+fn size(vertices: &[Point]) -> (i128, i128) {
+    let (min, max) = bounds(vertices);
+    (max.re - min.re + 1, max.im - min.im + 1)
+}
+
+fn bounds(vertices: &[Point]) -> (Point, Point) {
+    let min = Complex::new(
+        vertices.iter().map(|p| p.re).min().unwrap_or(0),
+        vertices.iter().map(|p| p.im).min().unwrap_or(0),
+    );
+    let max = Complex::new(
+        vertices.iter().map(|p| p.re).max().unwrap_or(0),
+        vertices.iter().map(|p| p.im).max().unwrap_or(0),
+    );
+    (min, max)
+}
+
+pub fn part1(input: Vec<Instr>) -> i128 {
+    Polygon::from_moves(input.iter().map(Instr::as_move))
+        .unwrap_or_else(|e| panic!("invalid dig plan: {e}"))
+        .volume()
+}
+
+pub fn part2(input: Vec<Instr>) -> i128 {
+    let moves = input
+        .iter()
+        .map(Instr::color_move)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| panic!("invalid color: {e}"));
+    Polygon::from_moves(moves)
+        .unwrap_or_else(|e| panic!("invalid dig plan: {e}"))
+        .volume()
 }
 
 #[cfg(test)]
@@ -151,4 +422,133 @@ mod tests {
         let input = data();
         assert_eq!(part2(parse_input(input)), 952408144115);
     }
+
+    #[test]
+    fn test_polygon() {
+        let polygon = Polygon::from_moves(parse_input(data()).iter().map(Instr::as_move)).unwrap();
+        assert_eq!(polygon.boundary(), 38);
+        assert_eq!(polygon.area(), 42);
+        assert_eq!(polygon.interior(), 24);
+        assert_eq!(polygon.volume(), 62);
+    }
+
+    #[test]
+    fn test_polygon_errors() {
+        use super::Direction::*;
+        assert_eq!(
+            Polygon::from_moves([(Right, 2), (Down, 2), (Left, 2)]),
+            Err(PolygonError::NotClosed {
+                end: Complex::new(0, 2)
+            })
+        );
+        assert_eq!(
+            Polygon::from_moves([(Right, 2), (Down, 0), (Left, 2)]),
+            Err(PolygonError::InvalidLength { index: 1, len: 0 })
+        );
+        assert_eq!(
+            Polygon::from_moves([(Right, 2), (Left, 2)]),
+            Err(PolygonError::SelfIntersecting {
+                first: 0,
+                second: 1
+            })
+        );
+        // figure eight
+        assert_eq!(
+            Polygon::from_moves([
+                (Right, 2),
+                (Down, 2),
+                (Right, 2),
+                (Down, 2),
+                (Left, 2),
+                (Up, 2),
+                (Left, 2),
+                (Up, 2)
+            ]),
+            Err(PolygonError::SelfIntersecting {
+                first: 1,
+                second: 5
+            })
+        );
+        let square = Polygon::from_moves([(Right, 1), (Down, 1), (Left, 1), (Up, 1)]).unwrap();
+        assert_eq!(square.volume(), 4);
+    }
+
+    #[test]
+    fn test_color_move() {
+        let instr = Instr::new(Direction::Right, 6, String::from("70c710"));
+        assert_eq!(instr.color_move(), Ok((Direction::Right, 461937)));
+        let instr = Instr::new(Direction::Right, 6, String::from("70c714"));
+        assert!(instr.color_move().is_err());
+    }
+
+    #[test]
+    fn test_rgb() {
+        let instr = Instr::new(Direction::Right, 6, String::from("70c710"));
+        assert_eq!(instr.rgb(), Ok([0x70, 0xc7, 0x10]));
+        let instr = Instr::new(Direction::Right, 6, String::from("70c7"));
+        assert!(instr.rgb().is_err());
+    }
+
+    #[test]
+    fn test_render_ppm() {
+        use super::Direction::*;
+        let polygon = Polygon::from_moves([(Right, 2), (Down, 2), (Left, 2), (Up, 2)]).unwrap();
+        let colors = [[1, 1, 1], [2, 2, 2], [3, 3, 3], [4, 4, 4]];
+        let expected = indoc! {
+        "P3
+        3 3
+        255
+        1 1 1 1 1 1 2 2 2
+        4 4 4 64 64 64 2 2 2
+        4 4 4 3 3 3 3 3 3
+        "};
+        assert_eq!(render_ppm(&polygon, &colors), Ok(expected.to_string()));
+        assert_eq!(
+            render(&polygon, &colors),
+            Ok(Image::Ppm(expected.to_string()))
+        );
+        assert_eq!(
+            render_ppm(&polygon, &colors[..3]),
+            Err("Expected 4 colors, got 3".to_string())
+        );
+        assert_eq!(
+            render(&polygon, &[[0, 0, 0]; 5]),
+            Err("Expected 4 colors, got 5".to_string())
+        );
+
+        let instrs = parse_input(data());
+        let polygon = Polygon::from_moves(instrs.iter().map(Instr::as_move)).unwrap();
+        let colors = instrs.iter().map(|i| i.rgb().unwrap()).collect::<Vec<_>>();
+        let ppm = render_ppm(&polygon, &colors).unwrap();
+        let values = ppm
+            .lines()
+            .skip(3)
+            .flat_map(|l| l.split(' '))
+            .collect::<Vec<_>>();
+        let dug = values
+            .chunks(3)
+            .filter(|p| *p != ["255", "255", "255"])
+            .count();
+        assert_eq!(dug, 62);
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let instrs = parse_input(data());
+        let moves = instrs
+            .iter()
+            .map(|i| i.color_move().unwrap())
+            .collect::<Vec<_>>();
+        let polygon = Polygon::from_moves(moves).unwrap();
+        let colors = instrs.iter().map(|i| i.rgb().unwrap()).collect::<Vec<_>>();
+        assert!(render_ppm(&polygon, &colors).is_err());
+        let svg = render_svg(&polygon, &colors).unwrap();
+        assert_eq!(render(&polygon, &colors), Ok(Image::Svg(svg.clone())));
+        assert!(render_svg(&polygon, &colors[1..]).is_err());
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 1186329 1186329\">"
+        ));
+        assert_eq!(svg.matches("<line").count(), 14);
+        assert!(svg.contains("x1=\"0\" y1=\"0\" x2=\"461937\" y2=\"0\" stroke=\"#70c710\""));
+    }
 }
@@ -1,5 +1,4 @@
 use itertools::Itertools;
-use memoize::memoize;
 use num::complex::Complex;
 use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
 use std::collections::VecDeque;
@@ -32,7 +31,6 @@ pub fn parse_input(input: &str) -> (Coord, HashSet<Coord>) {
     (start, m)
 }
 
-#[memoize(Ignore: map)]
 fn possible_next(pos: Coord, map: &HashSet<Coord>) -> Vec<Coord> {
     [pos + LEFT, pos + RIGHT, pos + UP, pos + DOWN]
         .into_iter()
@@ -44,7 +42,8 @@ pub fn part1((start, map): (Coord, HashSet<Coord>)) -> usize {
     visit(start, map, 64)
 }
 
-fn visit_all(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
+/// Plots reached in exactly `max_iter` steps, trying every path
+pub fn visit_all(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
     let mut reached: HashSet<Complex<isize>> = HashSet::default();
     let mut queue = Vec::from_iter([(0, start)]);
     while let Some((iteration, pos)) = queue.pop() {
@@ -58,11 +57,16 @@ fn visit_all(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
             queue.push((step, p))
         }
     }
-    println!("{:?}", reached);
     reached.len()
 }
 
-fn visit(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
+/// Plots reached in exactly `max_iter` steps: those at a distance of the
+/// same parity, going back and forth to spend the extra steps
+pub fn visit(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
+    // a walled in start cannot go back and forth
+    if max_iter > 0 && possible_next(start, &map).is_empty() {
+        return 0;
+    }
     let mut seen: HashMap<Coord, usize> = HashMap::default();
     let mut queue = VecDeque::from_iter([(0, start)]);
     while let Some((mut iteration, pos)) = queue.pop_front() {
@@ -92,13 +96,20 @@ fn visit(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
             }
         }
     }
-    let filtered = seen.into_iter().filter(|(_, n)| n % 2 == 0).collect_vec();
+    let filtered = seen
+        .into_iter()
+        .filter(|(_, n)| n % 2 == max_iter % 2)
+        .collect_vec();
     filtered.len()
 }
 
 fn visit_p2(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
     let max_right = map.iter().map(|x| x.re).max().unwrap() + 1;
     let max_down = map.iter().map(|x| x.im).max().unwrap() + 1;
+    let bottom_right = Complex::new(max_right, max_down);
+    if max_iter > 0 && possible_next_p2(start, &map, bottom_right).is_empty() {
+        return 0;
+    }
     let mut seen: HashMap<Coord, usize> = HashMap::default();
     let mut queue = VecDeque::from_iter([(0, start)]);
     while let Some((mut iteration, pos)) = queue.pop_front() {
@@ -123,16 +134,18 @@ fn visit_p2(start: Coord, map: HashSet<Coord>, max_iter: usize) -> usize {
         }
         let step = seen.get(&pos).unwrap_or(&iteration) + 1;
         if step <= max_iter {
-            for p in possible_next_p2(pos, &map, Complex::new(max_right, max_down)) {
+            for p in possible_next_p2(pos, &map, bottom_right) {
                 queue.push_back((step, p));
             }
         }
     }
-    let filtered = seen.into_iter().filter(|(_, n)| n % 2 == 0).collect_vec();
+    let filtered = seen
+        .into_iter()
+        .filter(|(_, n)| n % 2 == max_iter % 2)
+        .collect_vec();
     filtered.len()
 }
 
-#[memoize(Ignore: map)]
 fn possible_next_p2(pos: Coord, map: &HashSet<Coord>, bottom_right: Coord) -> Vec<Coord> {
     [pos + LEFT, pos + RIGHT, pos + UP, pos + DOWN]
         .into_iter()
@@ -191,9 +204,15 @@ mod tests {
         let input = data();
         let (start, map) = parse_input(input);
 
-        assert_eq!(visit(start, map.clone(), 1), 1);
+        assert_eq!(visit(start, map.clone(), 1), 2);
         assert_eq!(visit(start, map.clone(), 2), 4);
+        assert_eq!(visit(start, map.clone(), 3), 6);
         assert_eq!(visit(start, map.clone(), 6), 16);
+        let (start, map) = parse_input("###\n#S#\n##.");
+        assert_eq!(visit(start, map.clone(), 0), 1);
+        assert_eq!(visit(start, map.clone(), 1), 0);
+        assert_eq!(visit(start, map.clone(), 2), 0);
+        assert_eq!(visit_all(start, map, 2), 0);
     }
 
     #[test]
@@ -0,0 +1,278 @@
+//! Differential testing: solvers expected to agree, a slow reference against
+//! a faster rewrite, run on generated inputs. The first diverging input is
+//! shrunk as long as the solvers still disagree on it.
+use crate::generators::Generator;
+use crate::{day05, day12, day21};
+use num::Complex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rustc_hash::FxHashSet as HashSet;
+use std::fmt::Debug;
+
+/// Input on which the solvers disagree, with both answers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence<I, O> {
+    pub input: I,
+    pub reference: O,
+    pub candidate: O,
+}
+
+pub struct Pair<I, O> {
+    pub name: &'static str,
+    /// random input of the given size
+    pub generate: fn(&mut StdRng, usize) -> I,
+    /// strictly smaller inputs to try when minimising
+    pub shrink: fn(&I) -> Vec<I>,
+    pub reference: fn(&I) -> O,
+    pub candidate: fn(&I) -> O,
+}
+
+impl<I: Clone, O: PartialEq> Pair<I, O> {
+    fn diverges(&self, input: &I) -> Option<Divergence<I, O>> {
+        let (reference, candidate) = ((self.reference)(input), (self.candidate)(input));
+        (reference != candidate).then(|| Divergence {
+            input: input.clone(),
+            reference,
+            candidate,
+        })
+    }
+
+    /// Shrinks greedily until no smaller input diverges
+    fn minimise(&self, mut divergence: Divergence<I, O>) -> Divergence<I, O> {
+        while let Some(smaller) = (self.shrink)(&divergence.input)
+            .iter()
+            .find_map(|i| self.diverges(i))
+        {
+            divergence = smaller;
+        }
+        divergence
+    }
+
+    /// First diverging input, minimised
+    pub fn check(&self, inputs: impl IntoIterator<Item = I>) -> Result<(), Divergence<I, O>> {
+        match inputs.into_iter().find_map(|i| self.diverges(&i)) {
+            Some(divergence) => Err(self.minimise(divergence)),
+            None => Ok(()),
+        }
+    }
+}
+
+pub trait Differential {
+    fn name(&self) -> &str;
+
+    /// Runs `cases` generated inputs, describing the minimised divergence
+    /// if any
+    fn run(&self, seed: u64, cases: usize, size: usize) -> Result<(), String>;
+}
+
+impl<I: Clone + Debug, O: PartialEq + Debug> Differential for Pair<I, O> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn run(&self, seed: u64, cases: usize, size: usize) -> Result<(), String> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let inputs = (0..cases).map(|_| (self.generate)(&mut rng, size));
+        self.check(inputs).map_err(|d| {
+            format!(
+                "{}: {:?} gives {:?} instead of {:?}",
+                self.name, d.input, d.candidate, d.reference
+            )
+        })
+    }
+}
+
+/// Inputs without one element each
+fn without_one<T: Clone>(items: &[T]) -> impl Iterator<Item = Vec<T>> + '_ {
+    (0..items.len()).map(|i| [&items[..i], &items[i + 1..]].concat())
+}
+
+type Springs = (String, Vec<usize>);
+
+fn springs(rng: &mut StdRng, size: usize) -> Vec<Springs> {
+    day12::parse_input(&Generator::new(rng.gen(), size).day12())
+}
+
+fn shrink_row((row, groups): &Springs) -> Vec<Springs> {
+    let chars = row.chars().collect::<Vec<_>>();
+    let shorter = without_one(&chars).map(|c| (c.into_iter().collect(), groups.clone()));
+    let fewer_groups = without_one(groups).map(|g| (row.clone(), g));
+    let known = row
+        .match_indices('?')
+        .map(|(i, _)| (format!("{}.{}", &row[..i], &row[i + 1..]), groups.clone()));
+    shorter.chain(fewer_groups).chain(known).collect()
+}
+
+fn shrink_springs(rows: &[Springs]) -> Vec<Vec<Springs>> {
+    let fewer_rows = without_one(rows).filter(|r| !r.is_empty());
+    let smaller_rows = rows.iter().enumerate().flat_map(|(i, row)| {
+        shrink_row(row).into_iter().map(move |row| {
+            let mut rows = rows.to_vec();
+            rows[i] = row;
+            rows
+        })
+    });
+    fewer_rows.chain(smaller_rows).collect()
+}
+
+fn arrangements(rows: &[Springs], count: fn(&[u8], &[usize]) -> usize) -> Vec<usize> {
+    rows.iter()
+        .map(|(row, groups)| count(row.as_bytes(), groups))
+        .collect()
+}
+
+type Garden = (String, usize);
+
+fn garden(rng: &mut StdRng, size: usize) -> Garden {
+    let map = Generator::new(rng.gen(), size.min(9)).day21();
+    (map, rng.gen_range(0..=6))
+}
+
+fn shrink_garden((map, steps): &Garden) -> Vec<Garden> {
+    let fewer_steps = steps.checked_sub(1).map(|s| (map.clone(), s));
+    let fewer_rocks = map
+        .match_indices('#')
+        .map(|(i, _)| (format!("{}.{}", &map[..i], &map[i + 1..]), *steps));
+    fewer_steps.into_iter().chain(fewer_rocks).collect()
+}
+
+fn steps_with(
+    (map, steps): &Garden,
+    visit: fn(Complex<isize>, HashSet<Complex<isize>>, usize) -> usize,
+) -> usize {
+    let (start, map) = day21::parse_input(map);
+    visit(start, map, *steps)
+}
+
+type Almanac = (Vec<isize>, Vec<(String, String, Vec<day05::Mapping>)>);
+
+fn almanac(rng: &mut StdRng, size: usize) -> Almanac {
+    day05::parse_input(&Generator::new(rng.gen(), size).day05())
+}
+
+fn shrink_almanac((seeds, maps): &Almanac) -> Vec<Almanac> {
+    let fewer_seeds = without_one(seeds)
+        .filter(|s| !s.is_empty())
+        .map(|s| (s, maps.clone()));
+    let fewer_mappings = maps.iter().enumerate().flat_map(|(i, (source, dest, m))| {
+        without_one(m).map(move |m| {
+            let mut maps = maps.clone();
+            maps[i] = (source.clone(), dest.clone(), m);
+            (seeds.clone(), maps)
+        })
+    });
+    fewer_seeds.chain(fewer_mappings).collect()
+}
+
+fn lowest_location(input: &Almanac) -> isize {
+    let seeds = input.0.iter().map(|&s| s..s + 1).collect::<Vec<_>>();
+    day05::IntervalMap::chain(&input.1, "seed", "location")
+        .unwrap()
+        .map_ranges(&seeds)[0]
+        .start
+}
+
+/// Every registered pair of equivalent solvers
+pub fn registry() -> Vec<Box<dyn Differential>> {
+    vec![
+        Box::new(Pair {
+            name: "day12 arrangements",
+            generate: springs,
+            shrink: |rows| shrink_springs(rows),
+            reference: |rows| arrangements(rows, day12::count_brute_force),
+            candidate: |rows| arrangements(rows, day12::count_arrangements),
+        }),
+        Box::new(Pair {
+            name: "day21 garden plots",
+            generate: garden,
+            shrink: shrink_garden,
+            reference: |input| steps_with(input, day21::visit_all),
+            candidate: |input| steps_with(input, day21::visit),
+        }),
+        Box::new(Pair {
+            name: "day05 lowest location",
+            generate: almanac,
+            shrink: shrink_almanac,
+            reference: |input| day05::part1(input.clone()),
+            candidate: lowest_location,
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_registry_agrees() {
+        for pair in registry() {
+            assert_eq!(pair.run(42, 200, 8), Ok(()), "{}", pair.name());
+        }
+    }
+
+    #[test]
+    fn test_minimised_divergence() {
+        let pair = Pair {
+            name: "sum",
+            generate: |rng, size| (0..size).map(|_| rng.gen_range(0..10)).collect(),
+            shrink: |v: &Vec<u32>| without_one(v).collect(),
+            reference: |v| v.iter().sum::<u32>(),
+            candidate: |v| v.iter().filter(|&&x| x != 7).sum::<u32>(),
+        };
+        assert_eq!(pair.check([vec![1, 2], vec![3]]), Ok(()));
+        assert_eq!(
+            pair.check([vec![1, 2], vec![3, 7, 4, 7]]),
+            Err(Divergence {
+                input: vec![7],
+                reference: 7,
+                candidate: 0
+            })
+        );
+        assert_eq!(
+            pair.run(1, 50, 10),
+            Err("sum: [7] gives 0 instead of 7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_shrink_springs() {
+        let smaller = shrink_row(&("#?".to_string(), vec![1]));
+        assert_eq!(
+            smaller,
+            vec![
+                ("?".to_string(), vec![1]),
+                ("#".to_string(), vec![1]),
+                ("#?".to_string(), vec![]),
+                ("#.".to_string(), vec![1]),
+            ]
+        );
+        let rows = vec![("#".to_string(), vec![1]), ("?".to_string(), vec![])];
+        assert_eq!(
+            shrink_springs(&rows),
+            vec![
+                vec![("?".to_string(), vec![])],
+                vec![("#".to_string(), vec![1])],
+                vec![("".to_string(), vec![1]), ("?".to_string(), vec![])],
+                vec![("#".to_string(), vec![]), ("?".to_string(), vec![])],
+                vec![("#".to_string(), vec![1]), ("".to_string(), vec![])],
+                vec![("#".to_string(), vec![1]), (".".to_string(), vec![])],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shrink_garden() {
+        let garden = ("S#\n#.\n".to_string(), 1);
+        assert_eq!(
+            shrink_garden(&garden),
+            vec![
+                ("S#\n#.\n".to_string(), 0),
+                ("S.\n#.\n".to_string(), 1),
+                ("S#\n..\n".to_string(), 1),
+            ]
+        );
+        // a walled in start, once found diverging by the registry
+        assert_eq!(steps_with(&garden, day21::visit), 0);
+        assert_eq!(steps_with(&garden, day21::visit_all), 0);
+    }
+}
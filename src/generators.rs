@@ -0,0 +1,1387 @@
+//! Random but valid puzzle inputs, reproducible from a seed. `size` scales
+//! the number of lines, cards, modules... of each input.
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rustc_hash::FxHashSet as HashSet;
+use std::fmt::Write;
+
+const DIGIT_WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+const ALMANAC: [&str; 8] = [
+    "seed",
+    "soil",
+    "fertilizer",
+    "water",
+    "light",
+    "temperature",
+    "humidity",
+    "location",
+];
+
+/// Distinct names given by `Generator::name`
+const NAMES: usize = 26 * 26 * 26;
+
+pub struct Generator {
+    rng: StdRng,
+    size: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64, size: usize) -> Self {
+        Generator {
+            rng: StdRng::seed_from_u64(seed),
+            size: size.max(1),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Three letters name, never clashing with `in` or the puzzle keywords,
+    /// distinct for `i` below `NAMES`
+    fn name(i: usize) -> String {
+        (0..3)
+            .map(|k| (b'a' + (i / 26_usize.pow(2 - k) % 26) as u8) as char)
+            .collect()
+    }
+
+    fn letters(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| self.rng.gen_range(b'a'..=b'z') as char)
+            .collect()
+    }
+
+    /// Square grid of tiles picked uniformly from `tiles`, repeat a tile to
+    /// make it more frequent
+    fn grid(&mut self, size: usize, tiles: &[u8]) -> Vec<Vec<u8>> {
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| *tiles.choose(&mut self.rng).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// `total` cut in `parts` random parts, each between `lo` and `hi`
+    fn split(&mut self, total: usize, parts: usize, lo: usize, hi: usize) -> Vec<usize> {
+        let mut res = vec![lo; parts];
+        for _ in 0..total - parts * lo {
+            let i = loop {
+                let i = self.rng.gen_range(0..parts);
+                if res[i] < hi {
+                    break i;
+                }
+            };
+            res[i] += 1;
+        }
+        res
+    }
+
+    /// Calibration lines mixing letters, digits and spelled digits, with at
+    /// least one ascii digit
+    pub fn day01(&mut self) -> String {
+        let mut res = String::new();
+        for _ in 0..self.size {
+            let mut line = String::new();
+            let digit_at = self.rng.gen_range(0..4);
+            for k in 0..4 {
+                match self.rng.gen_range(0..3) {
+                    _ if k == digit_at => line.push(self.rng.gen_range('1'..='9')),
+                    0 => line.push_str(DIGIT_WORDS.choose(&mut self.rng).unwrap()),
+                    _ => {
+                        let len = self.rng.gen_range(1..4);
+                        line.push_str(&self.letters(len));
+                    }
+                }
+            }
+            writeln!(res, "{line}").unwrap();
+        }
+        res
+    }
+
+    pub fn day02(&mut self) -> String {
+        let mut res = String::new();
+        for id in 1..=self.size {
+            let draws = (0..self.rng.gen_range(1..=6))
+                .map(|_| {
+                    let mut colors = ["red", "green", "blue"];
+                    colors.shuffle(&mut self.rng);
+                    let len = self.rng.gen_range(1..=3);
+                    colors[..len]
+                        .iter()
+                        .map(|c| format!("{} {c}", self.rng.gen_range(1..=20)))
+                        .join(", ")
+                })
+                .join("; ");
+            writeln!(res, "Game {id}: {draws}").unwrap();
+        }
+        res
+    }
+
+    /// Schematic of `size` lines of numbers of at most 3 digits, symbols and
+    /// dots
+    pub fn day03(&mut self) -> String {
+        let mut res = String::new();
+        for _ in 0..self.size {
+            let mut digits = 0;
+            for _ in 0..self.size {
+                let c = match self.rng.gen_range(0..10) {
+                    0..=2 if digits < 3 => self.rng.gen_range('0'..='9'),
+                    3 => *b"*#+$/=%@&-".choose(&mut self.rng).unwrap() as char,
+                    _ => '.',
+                };
+                digits = if c.is_ascii_digit() { digits + 1 } else { 0 };
+                res.push(c);
+            }
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Scratchcards never winning copies past the end of the table
+    pub fn day04(&mut self) -> String {
+        let mut res = String::new();
+        let width = self.size.to_string().len();
+        for i in 0..self.size {
+            let mut numbers = (1..100).collect::<Vec<u16>>();
+            numbers.shuffle(&mut self.rng);
+            let (winning, others) = numbers.split_at(5);
+            let matches = self.rng.gen_range(0..=5.min(self.size - 1 - i));
+            let mut got = winning[..matches].to_vec();
+            got.extend(&others[..8 - matches]);
+            got.shuffle(&mut self.rng);
+            let winning = winning.iter().map(|n| format!("{n:>2}")).join(" ");
+            let got = got.iter().map(|n| format!("{n:>2}")).join(" ");
+            writeln!(res, "Card {:>width$}: {winning} | {got}", i + 1).unwrap();
+        }
+        res
+    }
+
+    /// Almanac where each block is a permutation of `0..span`, cut in
+    /// shuffled pieces
+    pub fn day05(&mut self) -> String {
+        let span = 100 * self.size as isize;
+        let seeds = (0..self.size)
+            .flat_map(|_| {
+                let start = self.rng.gen_range(0..span);
+                [start, self.rng.gen_range(1..=span - start)]
+            })
+            .join(" ");
+        let mut res = format!("seeds: {seeds}\n");
+        for (source, dest) in ALMANAC.iter().tuple_windows() {
+            let cuts = self.rng.gen_range(1..=self.size + 1);
+            let bounds = (0..cuts)
+                .map(|_| self.rng.gen_range(1..span))
+                .chain([0, span])
+                .sorted_unstable()
+                .dedup()
+                .collect::<Vec<_>>();
+            let pieces = bounds.windows(2).map(|w| (w[0], w[1] - w[0])).collect_vec();
+            let mut order = (0..pieces.len()).collect::<Vec<_>>();
+            order.shuffle(&mut self.rng);
+            let mut start = 0;
+            let mut lines = vec![];
+            for i in order {
+                let (src, len) = pieces[i];
+                lines.push(format!("{start} {src} {len}"));
+                start += len;
+            }
+            lines.shuffle(&mut self.rng);
+            write!(res, "\n{source}-to-{dest} map:\n{}\n", lines.join("\n")).unwrap();
+        }
+        res
+    }
+
+    /// At most 4 races like the puzzle, each record below the best distance
+    pub fn day06(&mut self) -> String {
+        let races = (0..self.size.min(4))
+            .map(|_| {
+                let time = self.rng.gen_range(2..100);
+                (time, self.rng.gen_range(0..time * time / 4))
+            })
+            .collect::<Vec<(usize, usize)>>();
+        let times = races.iter().map(|(t, _)| format!("{t:>5}")).join("");
+        let records = races.iter().map(|(_, d)| format!("{d:>5}")).join("");
+        format!("Time:     {times}\nDistance: {records}\n")
+    }
+
+    /// Distinct hands like the puzzle, so that the ranking is unambiguous
+    pub fn day07(&mut self) -> String {
+        let labels = "23456789TJQKA".chars().collect::<Vec<_>>();
+        let mut hands = HashSet::default();
+        let mut res = String::new();
+        while hands.len() < self.size {
+            let hand = (0..5)
+                .map(|_| labels.choose(&mut self.rng).unwrap())
+                .collect::<String>();
+            if hands.insert(hand.clone()) {
+                writeln!(res, "{hand} {}", self.rng.gen_range(1..=1000)).unwrap();
+            }
+        }
+        res
+    }
+
+    /// Network where each node leads to later nodes, down to `ZZZ` looping on
+    /// itself. The ghosts start from `AAA` and up to 3 other nodes. Names
+    /// keep two letters of `name`, so there are at most 675 other nodes.
+    pub fn day08(&mut self) -> String {
+        let count = self.size.min(NAMES / 26 - 1) + 1;
+        let starts = (0..3)
+            .map(|_| self.rng.gen_range(0..count))
+            .collect::<HashSet<_>>();
+        let names = (0..=count)
+            .map(|i| match i {
+                0 => "AAA".to_string(),
+                _ if i == count => "ZZZ".to_string(),
+                _ => {
+                    let last = if starts.contains(&i) { 'a' } else { 'm' };
+                    (Self::name(i)[1..].to_string() + &last.to_string()).to_uppercase()
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut nodes = (0..count)
+            .map(|i| {
+                let [left, right] = [(); 2].map(|_| &names[self.rng.gen_range(i + 1..=count)]);
+                format!("{} = ({left}, {right})", names[i])
+            })
+            .collect::<Vec<_>>();
+        nodes.push("ZZZ = (ZZZ, ZZZ)".to_string());
+        nodes.shuffle(&mut self.rng);
+        let directions = (0..self.rng.gen_range(1..=5))
+            .map(|_| if self.rng.gen_bool(0.5) { 'L' } else { 'R' })
+            .collect::<String>();
+        format!("{directions}\n\n{}\n", nodes.join("\n"))
+    }
+
+    /// Sequences of 21 values of polynomials of degree at most 5
+    pub fn day09(&mut self) -> String {
+        let mut res = String::new();
+        for _ in 0..self.size {
+            let degree = self.rng.gen_range(0..=5);
+            let mut row = (0..=degree)
+                .map(|_| self.rng.gen_range(-9..=9))
+                .collect::<Vec<isize>>();
+            let mut values = vec![];
+            for _ in 0..21 {
+                values.push(row[0]);
+                // next forward differences at the next index
+                for j in 0..degree {
+                    row[j] += row[j + 1];
+                }
+            }
+            writeln!(res, "{}", values.iter().join(" ")).unwrap();
+        }
+        res
+    }
+
+    /// Clockwise loop of unit steps around a vertically convex shape, from
+    /// its top left corner
+    fn convex_loop(&mut self, width: usize, height: isize) -> Vec<(isize, isize)> {
+        let mut columns: Vec<(isize, isize)> = vec![];
+        for _ in 0..width {
+            let (prev_top, prev_bottom) = columns.last().copied().unwrap_or((0, height));
+            let top = self.rng.gen_range(0..prev_bottom);
+            let bottom = self.rng.gen_range(top.max(prev_top) + 1..=height);
+            columns.push((top, bottom));
+        }
+        let mut path = vec![];
+        let mut pos = (0, columns[0].0);
+        let mut go = |path: &mut Vec<(isize, isize)>, to: (isize, isize)| {
+            while pos != to {
+                path.push(pos);
+                pos.0 += (to.0 - pos.0).signum();
+                pos.1 += (to.1 - pos.1).signum();
+            }
+        };
+        for (x, &(top, _)) in columns.iter().enumerate() {
+            go(&mut path, (x as isize, top));
+            go(&mut path, (x as isize + 1, top));
+        }
+        for (x, &(_, bottom)) in columns.iter().enumerate().rev() {
+            go(&mut path, (x as isize + 1, bottom));
+            go(&mut path, (x as isize, bottom));
+        }
+        go(&mut path, (0, columns[0].0));
+        path
+    }
+
+    /// Loop around a vertically convex shape `size` tiles wide and high, with
+    /// junk pipes around that never connect to the start
+    pub fn day10(&mut self) -> String {
+        let (width, height) = (self.size.max(2), self.size.max(2) as isize);
+        let path = self.convex_loop(width, height);
+        let (cols, rows) = (width + 3, height as usize + 3);
+        let mut grid = (0..rows)
+            .map(|_| {
+                (0..cols)
+                    .map(|_| *b"|-LJ7F...".choose(&mut self.rng).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for (i, &(x, y)) in path.iter().enumerate() {
+            let prev = path[(i + path.len() - 1) % path.len()];
+            let next = path[(i + 1) % path.len()];
+            let dirs = [prev, next].map(|(px, py)| (px - x, py - y));
+            let pipe = match dirs {
+                [(0, _), (0, _)] => b'|',
+                [(_, 0), (_, 0)] => b'-',
+                _ if dirs.contains(&(0, -1)) && dirs.contains(&(1, 0)) => b'L',
+                _ if dirs.contains(&(0, -1)) => b'J',
+                _ if dirs.contains(&(-1, 0)) => b'7',
+                _ => b'F',
+            };
+            grid[y as usize + 1][x as usize + 1] = pipe;
+        }
+        let (sx, sy) = *path.choose(&mut self.rng).unwrap();
+        let (sx, sy) = (sx as usize + 1, sy as usize + 1);
+        grid[sy][sx] = b'S';
+        let on_loop = path.iter().collect::<HashSet<_>>();
+        // junk pointing to the start would make it ambiguous
+        for (dx, dy, pointing) in [(0, -1, "|7F"), (1, 0, "-J7"), (0, 1, "|LJ"), (-1, 0, "-LF")] {
+            let (x, y) = (sx as isize + dx, sy as isize + dy);
+            let tile = &mut grid[y as usize][x as usize];
+            if !on_loop.contains(&(x - 1, y - 1)) && pointing.as_bytes().contains(tile) {
+                *tile = b'.';
+            }
+        }
+        to_text(grid)
+    }
+
+    pub fn day11(&mut self) -> String {
+        let mut res = String::new();
+        for _ in 0..self.size {
+            let row = (0..self.size)
+                .map(|_| if self.rng.gen_ratio(1, 8) { '#' } else { '.' })
+                .collect::<String>();
+            writeln!(res, "{row}").unwrap();
+        }
+        res
+    }
+
+    /// Rows of at most 14 springs, short enough to try every arrangement
+    pub fn day12(&mut self) -> String {
+        let mut res = String::new();
+        for _ in 0..self.size {
+            let len = self.rng.gen_range(1..=14);
+            let row = (0..len)
+                .map(|_| *b".#?".choose(&mut self.rng).unwrap() as char)
+                .collect::<String>();
+            let groups = (0..self.rng.gen_range(1..=3))
+                .map(|_| self.rng.gen_range(1..=3))
+                .join(",");
+            writeln!(res, "{row} {groups}").unwrap();
+        }
+        res
+    }
+
+    /// Patterns made symmetric around a random mirror line, then smudged
+    /// half of the time
+    pub fn day13(&mut self) -> String {
+        let mut patterns = vec![];
+        for _ in 0..self.size {
+            let (width, height) = (self.rng.gen_range(2..=17), self.rng.gen_range(2..=17));
+            let mut grid = self.grid(width.max(height), b"#.");
+            grid.truncate(height);
+            grid.iter_mut().for_each(|row| row.truncate(width));
+            if self.rng.gen_bool(0.5) {
+                let index = self.rng.gen_range(1..height);
+                for k in 0..index.min(height - index) {
+                    grid[index + k] = grid[index - 1 - k].clone();
+                }
+            } else {
+                let index = self.rng.gen_range(1..width);
+                for row in &mut grid {
+                    for k in 0..index.min(width - index) {
+                        row[index + k] = row[index - 1 - k];
+                    }
+                }
+            }
+            if self.rng.gen_bool(0.5) {
+                let (x, y) = (self.rng.gen_range(0..width), self.rng.gen_range(0..height));
+                grid[y][x] = if grid[y][x] == b'#' { b'.' } else { b'#' };
+            }
+            patterns.push(to_text(grid));
+        }
+        patterns.join("\n")
+    }
+
+    pub fn day14(&mut self) -> String {
+        let grid = self.grid(self.size, b"OO#.......");
+        to_text(grid)
+    }
+
+    pub fn day15(&mut self) -> String {
+        let steps = (0..self.size)
+            .map(|_| {
+                let len = self.rng.gen_range(1..=6);
+                let label = self.letters(len);
+                if self.rng.gen_bool(0.3) {
+                    format!("{label}-")
+                } else {
+                    format!("{label}={}", self.rng.gen_range(1..=9))
+                }
+            })
+            .join(",");
+        steps + "\n"
+    }
+
+    pub fn day16(&mut self) -> String {
+        let grid = self.grid(self.size, b"-|/\\............");
+        to_text(grid)
+    }
+
+    /// Heat loss map with a path of 1s for each crucible from the top left
+    /// to the bottom right corner, so that both parts lose `2 * (size - 1)`.
+    /// At least 5 blocks wide for the ultra crucible to fit.
+    pub fn day17(&mut self) -> String {
+        let size = self.size.max(5);
+        let mut grid = self.grid(size, b"123456789");
+        let moves = size - 1;
+        for (lo, hi) in [(1, 3), (4, 10)] {
+            let segments = self.rng.gen_range(moves.div_ceil(hi)..=moves / lo);
+            let right = self.split(moves, segments, lo, hi);
+            let down = self.split(moves, segments, lo, hi);
+            let (mut x, mut y) = (0, 0);
+            for (r, d) in right.into_iter().zip(down) {
+                for _ in 0..r {
+                    x += 1;
+                    grid[y][x] = b'1';
+                }
+                for _ in 0..d {
+                    y += 1;
+                    grid[y][x] = b'1';
+                }
+            }
+        }
+        to_text(grid)
+    }
+
+    /// Dig plan around a vertically convex shape, the colours encoding the
+    /// same plan scaled up by a random factor
+    pub fn day18(&mut self) -> String {
+        let size = self.size.max(2);
+        let path = self.convex_loop(size, size as isize);
+        let scale = self.rng.gen_range(2..=1000);
+        let steps = path
+            .iter()
+            .zip(path.iter().cycle().skip(1))
+            .map(|(a, b)| (b.0 - a.0, b.1 - a.1))
+            .group_by(|&step| step);
+        let mut res = String::new();
+        // the path starts on a corner, so no move wraps around
+        for (step, group) in &steps {
+            let len = group.count();
+            let (dir, code) = match step {
+                (1, 0) => ('R', 0),
+                (0, 1) => ('D', 1),
+                (-1, 0) => ('L', 2),
+                _ => ('U', 3),
+            };
+            writeln!(res, "{dir} {len} (#{:05x}{code})", len * scale).unwrap();
+        }
+        res
+    }
+
+    /// Workflows only sending parts to later workflows, so every part ends
+    /// up accepted or rejected. At most `NAMES` workflows.
+    pub fn day19(&mut self) -> String {
+        let count = self.size.min(NAMES);
+        let dest = |rng: &mut StdRng, i: usize| match rng.gen_range(i + 1..count + 2) {
+            j if j == count => "A".to_string(),
+            j if j == count + 1 => "R".to_string(),
+            j => Self::name(j),
+        };
+        let mut res = String::new();
+        for i in 0..count {
+            let name = if i == 0 {
+                "in".to_string()
+            } else {
+                Self::name(i)
+            };
+            let rules = (0..self.rng.gen_range(1..=4))
+                .map(|_| {
+                    let category = *b"xmas".choose(&mut self.rng).unwrap() as char;
+                    let comp = if self.rng.gen_bool(0.5) { '<' } else { '>' };
+                    let val = self.rng.gen_range(1..=4000);
+                    format!("{category}{comp}{val}:{},", dest(&mut self.rng, i))
+                })
+                .collect::<String>();
+            writeln!(res, "{name}{{{rules}{}}}", dest(&mut self.rng, i)).unwrap();
+        }
+        res.push('\n');
+        for _ in 0..self.size {
+            let [x, m, a, s] = [(); 4].map(|_| self.rng.gen_range(1..=4000));
+            writeln!(res, "{{x={x},m={m},a={a},s={s}}}").unwrap();
+        }
+        res
+    }
+
+    /// Modules only sending pulses to later modules, so that each press of
+    /// the button ends. There is no `rx` module, for part 1 only. At most
+    /// `NAMES - 1` modules, the last name being left for the output.
+    pub fn day20(&mut self) -> String {
+        let count = self.size.min(NAMES - 1);
+        let destinations = |rng: &mut StdRng, from: usize| {
+            let len = rng.gen_range(1..=3);
+            (0..len)
+                .map(|_| Self::name(rng.gen_range(from..count + 1)))
+                .unique()
+                .join(", ")
+        };
+        let mut res = format!("broadcaster -> {}\n", destinations(&mut self.rng, 0));
+        for i in 0..count {
+            let kind = if self.rng.gen_bool(0.7) { '%' } else { '&' };
+            let dest = destinations(&mut self.rng, i + 1);
+            writeln!(res, "{kind}{} -> {dest}", Self::name(i)).unwrap();
+        }
+        res
+    }
+
+    /// Garden with rocks anywhere, the start may even be walled in
+    pub fn day21(&mut self) -> String {
+        let mut grid = self.grid(self.size, b"#..");
+        let (x, y) = (
+            self.rng.gen_range(0..self.size),
+            self.rng.gen_range(0..self.size),
+        );
+        grid[y][x] = b'S';
+        to_text(grid)
+    }
+
+    /// Non overlapping bricks on a 10x10 footprint
+    pub fn day22(&mut self) -> String {
+        let mut occupied = HashSet::default();
+        let mut res = String::new();
+        let mut placed = 0;
+        while placed < self.size {
+            let len = self.rng.gen_range(0..4);
+            let axis = self.rng.gen_range(0..3);
+            let start = (
+                self.rng.gen_range(0..10 - if axis == 0 { len } else { 0 }),
+                self.rng.gen_range(0..10 - if axis == 1 { len } else { 0 }),
+                self.rng.gen_range(1..=2 * self.size as isize),
+            );
+            let cells = (0..=len)
+                .map(|k| match axis {
+                    0 => (start.0 + k, start.1, start.2),
+                    1 => (start.0, start.1 + k, start.2),
+                    _ => (start.0, start.1, start.2 + k),
+                })
+                .collect::<Vec<_>>();
+            if cells.iter().any(|c| occupied.contains(c)) {
+                continue;
+            }
+            occupied.extend(cells.iter().copied());
+            let (a, b) = (cells[0], cells[cells.len() - 1]);
+            writeln!(res, "{},{},{}~{},{},{}", a.0, a.1, a.2, b.0, b.1, b.2).unwrap();
+            placed += 1;
+        }
+        res
+    }
+}
+
+fn to_text(grid: Vec<Vec<u8>>) -> String {
+    grid.into_iter()
+        .map(|row| String::from_utf8(row).unwrap() + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::Direction;
+    use crate::{
+        day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+        day14, day15, day16, day17, day18, day19, day20, day21, day22,
+    };
+    use itertools::iproduct;
+    use num::{BigInt, Complex};
+    use pretty_assertions::assert_eq;
+    use rustc_hash::FxHashMap as HashMap;
+    use std::collections::VecDeque;
+
+    /// Generators for every seed and size tried
+    fn generators() -> impl Iterator<Item = Generator> {
+        (0..5)
+            .cartesian_product([1, 2, 10, 40])
+            .map(|(seed, size)| Generator::new(seed, size))
+    }
+
+    #[test]
+    fn test_reproducible() {
+        assert_eq!(Generator::new(3, 10).day05(), Generator::new(3, 10).day05());
+        assert_ne!(Generator::new(3, 10).day05(), Generator::new(4, 10).day05());
+        assert_eq!(Generator::new(3, 0).size(), 1);
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Generator::name(0), "aaa");
+        assert_eq!(Generator::name(27), "abb");
+        assert_eq!(Generator::name(26 * 26 * 26 - 1), "zzz");
+    }
+
+    #[test]
+    fn test_names_stay_distinct() {
+        let (_, network) = day08::parse_input(&Generator::new(0, 700).day08());
+        assert_eq!(network.len(), 677);
+        let (workflows, _) = day19::parse_input(&Generator::new(0, NAMES + 5).day19());
+        assert_eq!(workflows.len(), NAMES);
+        let network = Generator::new(0, NAMES + 5).day20();
+        let modules = network.lines().map(|l| &l[1..4]).skip(1);
+        assert_eq!(modules.unique().count(), NAMES - 1);
+    }
+
+    #[test]
+    fn test_split() {
+        let mut gen = Generator::new(0, 1);
+        for _ in 0..20 {
+            let parts = gen.split(20, 3, 4, 10);
+            assert_eq!(parts.iter().sum::<usize>(), 20);
+            assert!(parts.iter().all(|p| (4..=10).contains(p)), "{parts:?}");
+        }
+    }
+
+    /// First and last digit of each line, spelled ones included if `words`
+    fn day01_brute_force(document: &str, words: bool) -> u16 {
+        let calibration = |line: &str| {
+            let digits = (0..line.len())
+                .filter_map(|i| {
+                    let rest = &line[i..];
+                    let spelled = || DIGIT_WORDS.iter().position(|w| rest.starts_with(w));
+                    match rest.chars().next()?.to_digit(10) {
+                        Some(d) => Some(d as u16),
+                        None if words => spelled().map(|p| p as u16 + 1),
+                        None => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            10 * digits[0] + digits[digits.len() - 1]
+        };
+        document.lines().map(calibration).sum()
+    }
+
+    #[test]
+    fn test_day01() {
+        for mut gen in generators() {
+            let document = gen.day01();
+            assert_eq!(day01::parse_input(&document).len(), gen.size());
+            let part1 = day01::part1(day01::parse_input(&document));
+            assert_eq!(part1, day01_brute_force(&document, false));
+            let part2 = day01::part1(day01::parse_input_p2(&document));
+            assert_eq!(part2, day01_brute_force(&document, true));
+        }
+    }
+
+    /// Both parts from the largest count of each colour, a missing colour
+    /// counting as 0
+    fn day02_brute_force(record: &str) -> (u32, u32) {
+        let (mut possible, mut power) = (0, 0);
+        for line in record.lines() {
+            let (game, draws) = line.split_once(": ").unwrap();
+            let mut largest = [0; 3];
+            for cubes in draws.split([',', ';']) {
+                let (count, color) = cubes.trim().split_once(' ').unwrap();
+                let i = ["red", "green", "blue"].iter().position(|&c| c == color);
+                let i = i.unwrap();
+                largest[i] = largest[i].max(count.parse().unwrap());
+            }
+            if largest[0] <= 12 && largest[1] <= 13 && largest[2] <= 14 {
+                possible += game["Game ".len()..].parse::<u32>().unwrap();
+            }
+            power += largest.iter().product::<u32>();
+        }
+        (possible, power)
+    }
+
+    #[test]
+    fn test_day02() {
+        for mut gen in generators() {
+            let record = gen.day02();
+            let (part1, part2) = day02_brute_force(&record);
+            assert_eq!(day02::part1(day02::parse_input(&record)), part1);
+            assert_eq!(day02::part2(day02::parse_input(&record)), part2);
+        }
+    }
+
+    /// Points and cards in the end, copying cards one table row at a time
+    fn day04_brute_force(table: &str) -> (usize, usize) {
+        let matches = table
+            .lines()
+            .map(|l| {
+                let (_, numbers) = l.split_once(": ").unwrap();
+                let (winning, got) = numbers.split_once(" | ").unwrap();
+                let winning = winning.split_whitespace().collect::<HashSet<_>>();
+                got.split_whitespace()
+                    .filter(|n| winning.contains(n))
+                    .count()
+            })
+            .collect::<Vec<_>>();
+        let points = matches.iter().filter(|&&m| m > 0).map(|m| 1 << (m - 1));
+        let mut copies = vec![1; matches.len()];
+        for (i, &m) in matches.iter().enumerate() {
+            for j in i + 1..=i + m {
+                copies[j] += copies[i];
+            }
+        }
+        (points.sum(), copies.iter().sum())
+    }
+
+    #[test]
+    fn test_day04() {
+        for mut gen in generators() {
+            let table = gen.day04();
+            let (part1, part2) = day04_brute_force(&table);
+            assert_eq!(day04::part1(day04::parse_input(&table)), part1);
+            assert_eq!(day04::part2(day04::parse_input(&table)), part2);
+        }
+    }
+
+    /// Both parts, scanning the numbers around each symbol
+    fn day03_brute_force(input: &str) -> (usize, usize) {
+        let grid = input.lines().map(str::as_bytes).collect::<Vec<_>>();
+        let mut numbers = vec![];
+        for (y, row) in grid.iter().enumerate() {
+            let runs = row.iter().enumerate().group_by(|(_, c)| c.is_ascii_digit());
+            for (_, run) in runs.into_iter().filter(|(digit, _)| *digit) {
+                let run = run.collect::<Vec<_>>();
+                let value = run
+                    .iter()
+                    .fold(0, |n, (_, &c)| 10 * n + (c - b'0') as usize);
+                numbers.push((y, run[0].0, run[0].0 + run.len(), value));
+            }
+        }
+        let mut part1: HashSet<&(usize, usize, usize, usize)> = HashSet::default();
+        let mut part2 = 0;
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                if c == b'.' || c.is_ascii_digit() {
+                    continue;
+                }
+                let near = numbers
+                    .iter()
+                    .filter(|&&(ny, start, end, _)| {
+                        y + 1 >= ny && y <= ny + 1 && x + 1 >= start && x <= end
+                    })
+                    .collect::<Vec<_>>();
+                part1.extend(near.iter().copied());
+                if c == b'*' && near.len() == 2 {
+                    part2 += near[0].3 * near[1].3;
+                }
+            }
+        }
+        (part1.iter().map(|n| n.3).sum(), part2)
+    }
+
+    #[test]
+    fn test_day03() {
+        for mut gen in generators() {
+            let schematic = gen.day03();
+            let (part1, part2) = day03_brute_force(&schematic);
+            assert_eq!(day03::part1(day03::parse_input(&schematic)), part1);
+            assert_eq!(day03::part2(day03::parse_input(&schematic)), part2);
+        }
+    }
+
+    #[test]
+    fn test_day05() {
+        for mut gen in generators() {
+            let (seeds, maps) = day05::parse_input(&gen.day05());
+            let almanac = day05::IntervalMap::chain(&maps, "seed", "location").unwrap();
+            let lowest = seeds.iter().map(|&s| almanac.get(s)).min();
+            assert_eq!(Some(day05::part1((seeds.clone(), maps.clone()))), lowest);
+            let lowest = seeds
+                .chunks(2)
+                .flat_map(|c| c[0]..c[0] + c[1])
+                .map(|s| almanac.get(s))
+                .min();
+            assert_eq!(Some(day05::part2((seeds, maps))), lowest);
+        }
+    }
+
+    #[test]
+    fn test_day05_permutation() {
+        let (_, maps) = day05::parse_input(&Generator::new(1, 5).day05());
+        let almanac = day05::IntervalMap::chain(&maps, "seed", "location").unwrap();
+        let mut locations = (0..500).map(|s| almanac.get(s)).collect::<Vec<_>>();
+        locations.sort_unstable();
+        assert_eq!(locations, (0..500).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_day06() {
+        for mut gen in generators() {
+            let races = day06::parse_input(&gen.day06());
+            assert_eq!(races.len(), gen.size().min(4));
+            let ways = races
+                .iter()
+                .map(|&(time, record)| (0..=time).filter(|h| h * (time - h) > record).count())
+                .product::<usize>();
+            assert_eq!(day06::part1(races), ways as f64);
+        }
+    }
+
+    /// Winnings sorting the hands by the counts of their labels, jokers
+    /// trying every label
+    fn day07_brute_force(hands: &str, jokers: bool) -> usize {
+        let order = if jokers {
+            "J23456789TQKA"
+        } else {
+            "23456789TJQKA"
+        };
+        let counts = |hand: &str| {
+            let mut counts = hand.chars().counts().into_values().collect::<Vec<_>>();
+            counts.sort_unstable_by(|a, b| b.cmp(a));
+            counts
+        };
+        let strength = |hand: &str| {
+            let counts = match jokers {
+                true => order
+                    .chars()
+                    .map(|j| counts(&hand.replace('J', &j.to_string())))
+                    .max()
+                    .unwrap(),
+                false => counts(hand),
+            };
+            let labels = hand.chars().map(|c| order.find(c).unwrap()).collect_vec();
+            (counts, labels)
+        };
+        hands
+            .lines()
+            .map(|l| l.split_once(' ').unwrap())
+            .map(|(hand, bid)| (strength(hand), bid.parse::<usize>().unwrap()))
+            .sorted()
+            .enumerate()
+            .map(|(i, (_, bid))| (i + 1) * bid)
+            .sum()
+    }
+
+    #[test]
+    fn test_day07() {
+        for mut gen in generators() {
+            let hands = gen.day07();
+            let part1 = day07::part1(day07::parse_input(&hands));
+            assert_eq!(part1, day07_brute_force(&hands, false));
+            let part2 = day07::part2(day07::parse_input_p2(&hands));
+            assert_eq!(part2, day07_brute_force(&hands, true));
+        }
+    }
+
+    #[test]
+    fn test_day08() {
+        for mut gen in generators() {
+            let (directions, network) = day08::parse_input(&gen.day08());
+            let steps_to_z = |start: &str| {
+                let (mut node, mut steps) = (start, 0);
+                while !node.ends_with('Z') {
+                    let (left, right) = network.successors(node).unwrap();
+                    node = match directions[steps % directions.len()] {
+                        day08::Dir::Left => left,
+                        day08::Dir::Right => right,
+                    };
+                    steps += 1;
+                }
+                steps
+            };
+            let ghosts = (0..network.len() as u32)
+                .map(|id| network.name(id))
+                .filter(|name| name.ends_with('A'))
+                .map(steps_to_z)
+                .max();
+            let part1 = steps_to_z("AAA");
+            let input = (directions.clone(), network.clone());
+            assert_eq!(day08::part1(input.clone()), part1);
+            assert_eq!(Some(day08::part2(input)), ghosts);
+        }
+    }
+
+    /// Next value by the difference table, going backwards on reversed values
+    fn extrapolate(values: &[isize]) -> isize {
+        if values.iter().all(|&v| v == 0) {
+            return 0;
+        }
+        let differences = values.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        values[values.len() - 1] + extrapolate(&differences)
+    }
+
+    #[test]
+    fn test_day09() {
+        for mut gen in generators() {
+            let history = day09::parse_input(&gen.day09());
+            let next = history.iter().map(|v| extrapolate(v)).sum::<isize>();
+            let previous = history
+                .iter()
+                .map(|v| extrapolate(&v.iter().rev().copied().collect::<Vec<_>>()))
+                .sum::<isize>();
+            assert_eq!(day09::part1(history.clone()), BigInt::from(next));
+            assert_eq!(day09::part2(history), BigInt::from(previous));
+        }
+    }
+
+    #[test]
+    fn test_day10() {
+        for mut gen in generators() {
+            let size = gen.size();
+            let maze = day10::parse_input(&gen.day10());
+            assert!(maze.main_loop().len() >= 2 * size.max(2) + 2);
+            let inside = maze
+                .tiles()
+                .iter()
+                .flatten()
+                .filter(|&t| *t == day10::Tile::Inside)
+                .count();
+            assert_eq!(day10::part2(maze), inside as isize);
+        }
+    }
+
+    /// Sum of the distances over every pair, counting empty lines `factor`
+    /// times
+    fn day11_brute_force(galaxies: &[Complex<isize>], factor: isize) -> isize {
+        let rows = galaxies.iter().map(|g| g.im).collect::<HashSet<_>>();
+        let cols = galaxies.iter().map(|g| g.re).collect::<HashSet<_>>();
+        let distance = |a: isize, b: isize, used: &HashSet<isize>| {
+            (a.min(b)..a.max(b))
+                .map(|k| if used.contains(&k) { 1 } else { factor })
+                .sum::<isize>()
+        };
+        galaxies
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| distance(a.re, b.re, &cols) + distance(a.im, b.im, &rows))
+            .sum()
+    }
+
+    #[test]
+    fn test_day11() {
+        for mut gen in generators() {
+            let galaxies = day11::parse_input(&gen.day11());
+            let (part1, part2) = (
+                day11_brute_force(&galaxies, 2),
+                day11_brute_force(&galaxies, 1_000_000),
+            );
+            assert_eq!(day11::part1(galaxies.clone()), part1);
+            assert_eq!(day11::part2(galaxies), part2);
+        }
+    }
+
+    #[test]
+    fn test_day12() {
+        for mut gen in generators() {
+            let rows = day12::parse_input(&gen.day12());
+            let total = rows
+                .iter()
+                .map(|(row, groups)| day12::count_brute_force(row.as_bytes(), groups))
+                .sum();
+            assert_eq!(day12::part1(rows), total);
+        }
+    }
+
+    /// Summary of the mirror lines with exactly `smudges` differences,
+    /// comparing every pair of cells
+    fn day13_brute_force(pattern: &[Vec<i8>], smudges: usize) -> usize {
+        let (height, width) = (pattern.len(), pattern[0].len());
+        let at = |x: usize, y: usize, transposed: bool| {
+            if transposed {
+                pattern[x][y]
+            } else {
+                pattern[y][x]
+            }
+        };
+        let mirrors = |len: usize, across: usize, transposed: bool| {
+            (1..len).filter(move |&i| {
+                let differences = (0..i.min(len - i))
+                    .cartesian_product(0..across)
+                    .filter(|&(k, x)| at(x, i - 1 - k, transposed) != at(x, i + k, transposed))
+                    .count();
+                differences == smudges
+            })
+        };
+        let rows = mirrors(height, width, false)
+            .map(|i| 100 * i)
+            .sum::<usize>();
+        rows + mirrors(width, height, true).sum::<usize>()
+    }
+
+    #[test]
+    fn test_day13() {
+        for mut gen in generators() {
+            let patterns = day13::parse_input(&gen.day13());
+            assert_eq!(patterns.len(), gen.size());
+            for smudges in [0, 1] {
+                let summary = patterns
+                    .iter()
+                    .map(|p| day13_brute_force(p, smudges))
+                    .sum::<usize>();
+                let summarize = [day13::part1, day13::part2][smudges];
+                assert_eq!(summarize(patterns.clone()), summary);
+            }
+        }
+    }
+
+    /// Rolls the rocks north
+    fn tilt(grid: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        let mut grid = grid.to_vec();
+        for x in 0..grid.len() {
+            let mut free = 0;
+            for y in 0..grid.len() {
+                match grid[y][x] {
+                    b'#' => free = y + 1,
+                    b'O' => {
+                        grid[y][x] = b'.';
+                        grid[free][x] = b'O';
+                        free += 1;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        grid
+    }
+
+    /// Turns the platform clockwise, so that west becomes north
+    fn turn(grid: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        (0..grid.len())
+            .map(|x| (0..grid.len()).rev().map(|y| grid[y][x]).collect())
+            .collect()
+    }
+
+    fn load(grid: &[Vec<u8>]) -> usize {
+        grid.iter()
+            .enumerate()
+            .map(|(y, row)| (grid.len() - y) * row.iter().filter(|&&c| c == b'O').count())
+            .sum()
+    }
+
+    #[test]
+    fn test_day14() {
+        for mut gen in generators() {
+            let platform = gen.day14();
+            let grid = platform
+                .lines()
+                .map(|l| l.as_bytes().to_vec())
+                .collect_vec();
+            let part1 = load(&tilt(&grid));
+            let mut seen = HashMap::default();
+            let mut states = vec![grid];
+            let spin = |g: &Vec<Vec<u8>>| (0..4).fold(g.clone(), |g, _| turn(&tilt(&g)));
+            let index = loop {
+                let last = states.last().unwrap();
+                if let Some(&start) = seen.get(last) {
+                    let period = states.len() - 1 - start;
+                    break start + (1_000_000_000 - start) % period;
+                }
+                seen.insert(last.clone(), states.len() - 1);
+                states.push(spin(last));
+            };
+            assert_eq!(day14::part1(day14::parse_input(&platform)), part1);
+            assert_eq!(
+                day14::part2(day14::parse_input(&platform)),
+                load(&states[index])
+            );
+        }
+    }
+
+    #[test]
+    fn test_day15() {
+        for mut gen in generators() {
+            let sequence = gen.day15();
+            let hash = |s: &str| s.bytes().fold(0, |acc, c| (acc + c as usize) * 17 % 256);
+            let steps = sequence.trim_end().split(',').collect::<Vec<_>>();
+            let mut boxes = vec![Vec::<(&str, usize)>::new(); 256];
+            for step in &steps {
+                if let Some(label) = step.strip_suffix('-') {
+                    boxes[hash(label)].retain(|&(l, _)| l != label);
+                } else {
+                    let (label, focal) = step.split_once('=').unwrap();
+                    let focal = focal.parse().unwrap();
+                    let lenses = &mut boxes[hash(label)];
+                    match lenses.iter_mut().find(|(l, _)| *l == label) {
+                        Some(lens) => lens.1 = focal,
+                        None => lenses.push((label, focal)),
+                    }
+                }
+            }
+            let power = boxes
+                .iter()
+                .enumerate()
+                .flat_map(|(i, lenses)| {
+                    lenses
+                        .iter()
+                        .enumerate()
+                        .map(move |(j, (_, focal))| (i + 1) * (j + 1) * focal)
+                })
+                .sum();
+            let part1 = steps.iter().map(|s| hash(s)).sum();
+            assert_eq!(day15::part1(day15::parse_input(&sequence)), part1);
+            assert_eq!(day15::part2(day15::parse_input_p2(&sequence)), power);
+        }
+    }
+
+    /// Energized tiles, following each beam one tile at a time
+    fn day16_brute_force(layout: &str) -> usize {
+        let grid = layout.lines().map(str::as_bytes).collect::<Vec<_>>();
+        let mut seen = HashSet::default();
+        let mut beams = vec![((0_isize, 0_isize), (1_isize, 0_isize))];
+        while let Some(((x, y), (dx, dy))) = beams.pop() {
+            let tile = grid.get(y as usize).and_then(|row| row.get(x as usize));
+            if tile.is_none() || !seen.insert(((x, y), (dx, dy))) {
+                continue;
+            }
+            let dirs = match (tile.unwrap(), dx) {
+                (b'/', _) => vec![(-dy, -dx)],
+                (b'\\', _) => vec![(dy, dx)],
+                (b'|', dx) if dx != 0 => vec![(0, 1), (0, -1)],
+                (b'-', 0) => vec![(1, 0), (-1, 0)],
+                _ => vec![(dx, dy)],
+            };
+            beams.extend(
+                dirs.into_iter()
+                    .map(|(dx, dy)| ((x + dx, y + dy), (dx, dy))),
+            );
+        }
+        seen.iter().map(|(pos, _)| pos).unique().count()
+    }
+
+    #[test]
+    fn test_day16() {
+        for mut gen in generators() {
+            let layout = gen.day16();
+            let part1 = day16::part1(day16::parse_input(&layout));
+            assert_eq!(part1, day16_brute_force(&layout));
+            let part2 = day16::part2(day16::parse_input(&layout));
+            assert!(part2 >= part1 && part2 <= gen.size().pow(2));
+        }
+    }
+
+    #[test]
+    fn test_day17() {
+        for mut gen in generators() {
+            let map = gen.day17();
+            let lowest = 2 * (gen.size().max(5) as u32 - 1);
+            assert_eq!(day17::part1(day17::parse_input(&map)), lowest);
+            assert_eq!(day17::part2(day17::parse_input(&map)), lowest);
+        }
+    }
+
+    /// Cubes dug, flooding the outside of the trench
+    fn day18_brute_force(plan: &[day18::Instr]) -> usize {
+        let mut trench = HashSet::default();
+        let mut pos = (0, 0);
+        for instr in plan {
+            let (dir, len) = instr.as_move();
+            let (dx, dy) = match dir {
+                Direction::Right => (1, 0),
+                Direction::Down => (0, 1),
+                Direction::Left => (-1, 0),
+                Direction::Up => (0, -1),
+            };
+            for _ in 0..len {
+                pos = (pos.0 + dx, pos.1 + dy);
+                trench.insert(pos);
+            }
+        }
+        let (min_x, max_x) = trench.iter().map(|p| p.0).minmax().into_option().unwrap();
+        let (min_y, max_y) = trench.iter().map(|p| p.1).minmax().into_option().unwrap();
+        let (xs, ys) = (min_x - 1..=max_x + 1, min_y - 1..=max_y + 1);
+        let mut outside = HashSet::from_iter([(min_x - 1, min_y - 1)]);
+        let mut queue = vec![(min_x - 1, min_y - 1)];
+        while let Some((x, y)) = queue.pop() {
+            for next in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if xs.contains(&next.0)
+                    && ys.contains(&next.1)
+                    && !trench.contains(&next)
+                    && outside.insert(next)
+                {
+                    queue.push(next);
+                }
+            }
+        }
+        xs.count() * ys.count() - outside.len()
+    }
+
+    #[test]
+    fn test_day18() {
+        for mut gen in generators() {
+            let plan = day18::parse_input(&gen.day18());
+            let volume = day18::part1(plan.clone());
+            assert_eq!(volume, day18_brute_force(&plan) as i128);
+            // a plan scaled by k has k^2 times the area and k times the boundary
+            let scale = plan[0].color_move().unwrap().1 / plan[0].as_move().1;
+            let boundary = plan.iter().map(|i| i.as_move().1).sum::<i128>();
+            let area = volume - boundary / 2 - 1;
+            let scaled = scale * scale * area + scale * boundary / 2 + 1;
+            assert_eq!(day18::part2(plan), scaled);
+        }
+    }
+
+    #[test]
+    fn test_day19() {
+        for mut gen in generators() {
+            let workflows = gen.day19();
+            // every part accepted by one system is rejected by the other
+            let swapped = workflows
+                .chars()
+                .map(|c| match c {
+                    'A' => 'R',
+                    'R' => 'A',
+                    c => c,
+                })
+                .collect::<String>();
+            let ratings = workflows
+                .lines()
+                .filter_map(|l| l.strip_prefix("{x="))
+                .flat_map(|l| l.trim_end_matches('}').split(','))
+                .map(|r| r.rsplit('=').next().unwrap().parse::<usize>().unwrap())
+                .sum();
+            let part1 = |input: &str| day19::part1(day19::parse_input(input));
+            assert_eq!(part1(&workflows) + part1(&swapped), ratings);
+            let part2 = |input: &str| day19::part2(day19::parse_input(input));
+            assert_eq!(part2(&workflows) + part2(&swapped), 4000_isize.pow(4));
+        }
+    }
+
+    /// Low times high pulses over 1000 presses, following each pulse
+    fn day20_brute_force(configuration: &str) -> usize {
+        let modules = configuration
+            .lines()
+            .map(|l| {
+                let (name, destinations) = l.split_once(" -> ").unwrap();
+                let destinations = destinations.split(", ").collect::<Vec<_>>();
+                (
+                    name.trim_start_matches(['%', '&']),
+                    (&name[..1], destinations),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let mut inputs = HashMap::<&str, Vec<&str>>::default();
+        for (&name, (_, destinations)) in &modules {
+            for &d in destinations {
+                inputs.entry(d).or_default().push(name);
+            }
+        }
+        let mut flip_flops = HashMap::<&str, bool>::default();
+        let mut memory = HashMap::<(&str, &str), bool>::default();
+        let (mut low, mut high) = (0, 0);
+        for _ in 0..1000 {
+            let mut pulses = VecDeque::from([("button", "broadcaster", false)]);
+            while let Some((from, to, pulse)) = pulses.pop_front() {
+                if pulse {
+                    high += 1;
+                } else {
+                    low += 1;
+                }
+                let Some((kind, destinations)) = modules.get(to) else {
+                    continue;
+                };
+                let sent = match *kind {
+                    "%" if pulse => continue,
+                    "%" => {
+                        let on = flip_flops.entry(to).or_default();
+                        *on = !*on;
+                        *on
+                    }
+                    "&" => {
+                        memory.insert((to, from), pulse);
+                        !inputs[to]
+                            .iter()
+                            .all(|&i| memory.get(&(to, i)) == Some(&true))
+                    }
+                    _ => pulse,
+                };
+                pulses.extend(destinations.iter().map(|&d| (to, d, sent)));
+            }
+        }
+        low * high
+    }
+
+    #[test]
+    fn test_day20() {
+        for mut gen in generators() {
+            let configuration = gen.day20();
+            let part1 = day20::part1(day20::parse_input(&configuration));
+            assert_eq!(part1, day20_brute_force(&configuration));
+        }
+    }
+
+    #[test]
+    fn test_day21() {
+        for mut gen in generators() {
+            let garden = gen.day21();
+            let (start, map) = day21::parse_input(&garden);
+            assert!(map.contains(&start));
+            assert_eq!(map.len(), garden.matches(['.', 'S']).count());
+            assert!(day21::part1((start, map.clone())) <= map.len());
+        }
+    }
+
+    type Cube = (isize, isize, isize);
+
+    /// Lowers the bricks one level at a time until none can, returns how
+    /// many moved
+    fn fall(bricks: &mut [Vec<Cube>]) -> usize {
+        let mut occupied = bricks.iter().flatten().copied().collect::<HashSet<_>>();
+        let mut moved = HashSet::default();
+        loop {
+            let mut changed = false;
+            for (i, brick) in bricks.iter_mut().enumerate() {
+                let below = brick.iter().map(|&(x, y, z)| (x, y, z - 1)).collect_vec();
+                if below
+                    .iter()
+                    .all(|c| c.2 >= 1 && (brick.contains(c) || !occupied.contains(c)))
+                {
+                    brick.iter().for_each(|c| {
+                        occupied.remove(c);
+                    });
+                    occupied.extend(below.iter().copied());
+                    *brick = below;
+                    moved.insert(i);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return moved.len();
+            }
+        }
+    }
+
+    /// Both parts, removing each brick in turn and letting the others fall
+    fn day22_brute_force(snapshot: &str) -> (usize, usize) {
+        let mut bricks = snapshot
+            .lines()
+            .map(|l| {
+                let (a, b) = l.split_once('~').unwrap();
+                let [a, b] = [a, b].map(|p| {
+                    let c = p.split(',').map(|v| v.parse().unwrap()).collect_vec();
+                    (c[0], c[1], c[2])
+                });
+                iproduct!(a.0..=b.0, a.1..=b.1, a.2..=b.2).collect_vec()
+            })
+            .collect_vec();
+        fall(&mut bricks);
+        let falling = (0..bricks.len())
+            .map(|i| fall(&mut [&bricks[..i], &bricks[i + 1..]].concat()))
+            .collect_vec();
+        (
+            falling.iter().filter(|&&n| n == 0).count(),
+            falling.iter().sum(),
+        )
+    }
+
+    #[test]
+    fn test_day22() {
+        for mut gen in generators() {
+            let snapshot = gen.day22();
+            assert!(snapshot
+                .lines()
+                .flat_map(|l| l.split(['~', ',']).step_by(3).take(2))
+                .all(|x| x.parse::<usize>().unwrap() < 10));
+            let (part1, part2) = day22_brute_force(&snapshot);
+            assert_eq!(day22::part1(day22::parse_input(&snapshot)), part1);
+            assert_eq!(day22::part2(day22::parse_input(&snapshot)), part2);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use num::{Complex, Integer, Signed};
+
+/// Edges of a closed polygon, the last vertex being linked back to the first.
+/// The first vertex can be repeated at the end or not.
+fn edges<T: Integer + Copy>(
+    vertices: &[Complex<T>],
+) -> impl Iterator<Item = (Complex<T>, Complex<T>)> + '_ {
+    let vertices = match vertices {
+        [first, rest @ .., last] if first == last => &vertices[..rest.len() + 1],
+        _ => vertices,
+    };
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(p, q)| (*p, *q))
+}
+
+/// Twice the signed area, positive when the vertices turn clockwise on screen
+fn double_area<T: Integer + Signed + Copy>(vertices: &[Complex<T>]) -> T {
+    // shoelace formula https://en.wikipedia.org/wiki/Shoelace_formula
+    // A &= \frac 1 2 \sum_{i=1}^n (x_iy_{i+1}-x_{i+1}y_i)
+    edges(vertices).fold(T::zero(), |acc, (p, q)| acc + p.re * q.im - q.re * p.im)
+}
+
+/// Area enclosed by a closed polygon, rounded down when it is not an integer
+/// (only with diagonal edges)
+pub fn shoelace_area<T: Integer + Signed + Copy>(vertices: &[Complex<T>]) -> T {
+    double_area(vertices).abs() / (T::one() + T::one())
+}
+
+/// Number of lattice points on the edges of a closed polygon
+pub fn boundary_points<T: Integer + Signed + Copy>(vertices: &[Complex<T>]) -> T {
+    edges(vertices).fold(T::zero(), |acc, (p, q)| {
+        acc + (q.re - p.re).abs().gcd(&(q.im - p.im).abs())
+    })
+}
+
+/// Number of lattice points strictly inside a closed polygon
+pub fn interior_points<T: Integer + Signed + Copy>(vertices: &[Complex<T>]) -> T {
+    // Pick's theorem https://en.wikipedia.org/wiki/Pick%27s_theorem
+    // A = i + b/2 - 1, so 2i = 2A - b + 2
+    let two = T::one() + T::one();
+    (double_area(vertices).abs() - boundary_points(vertices) + two) / two
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn square() -> Vec<Complex<isize>> {
+        vec![
+            Complex::new(0, 0),
+            Complex::new(3, 0),
+            Complex::new(3, 3),
+            Complex::new(0, 3),
+        ]
+    }
+
+    #[test]
+    fn test_shoelace_area() {
+        assert_eq!(shoelace_area(&square()), 9);
+        let mut reversed = square();
+        reversed.reverse();
+        assert_eq!(shoelace_area(&reversed), 9);
+        let mut closed = square();
+        closed.push(Complex::new(0, 0));
+        assert_eq!(shoelace_area(&closed), 9);
+        let triangle = [Complex::new(0, 0), Complex::new(1, 0), Complex::new(0, 1)];
+        assert_eq!(shoelace_area(&triangle), 0);
+        assert_eq!(shoelace_area::<isize>(&[]), 0);
+    }
+
+    #[test]
+    fn test_boundary_points() {
+        assert_eq!(boundary_points(&square()), 12);
+        let triangle = [Complex::new(0, 0), Complex::new(4, 0), Complex::new(0, 2)];
+        assert_eq!(boundary_points(&triangle), 4 + 2 + 2);
+    }
+
+    #[test]
+    fn test_interior_points() {
+        assert_eq!(interior_points(&square()), 4);
+        let triangle = [Complex::new(0, 0), Complex::new(4, 0), Complex::new(0, 2)];
+        // (1, 1) only
+        assert_eq!(interior_points(&triangle), 1);
+        let i128_square = square()
+            .into_iter()
+            .map(|c| Complex::new(c.re as i128, c.im as i128) * 1_000_000_000_000)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            interior_points(&i128_square),
+            (3_000_000_000_000 - 1) * (3_000_000_000_000 - 1)
+        );
+    }
+}
@@ -20,5 +20,8 @@ pub mod day19;
 pub mod day20;
 pub mod day21;
 pub mod day22;
+pub mod differential;
+pub mod generators;
+pub mod geometry;
 pub mod helpers;
 pub mod parsers;
@@ -30,8 +30,8 @@ const AOC: Aoc = Aoc {
         solution! {10, parser!{ day10::parse_input }, solver!{ day10::part1 }},
         solution! {10, parser!{ day10::parse_input }, solver!{ day10::part2 }},
         solution! {11, parser!{ day11::parse_input }, solver!{ day11::part1 }},
-        solution! {11, parser!{ day11::parse_input }, solver!{ day11::part1 }},
-        solution! {12, parser!{ day12::parse_input }, solver!{ day12::part1_recursive }},
+        solution! {11, parser!{ day11::parse_input }, solver!{ day11::part2 }},
+        solution! {12, parser!{ day12::parse_input }, solver!{ day12::part1 }},
         solution! {12, parser!{ day12::parse_input }, solver!{ day12::part2 }},
         solution! {13, parser!{ day13::parse_input }, solver!{ day13::part1 }},
         solution! {13, parser!{ day13::parse_input }, solver!{ day13::part2 }},
@@ -44,6 +44,7 @@ const AOC: Aoc = Aoc {
         solution! {17, parser!{ day17::parse_input }, solver!{ day17::part1 }},
         solution! {17, parser!{ day17::parse_input }, solver!{ day17::part1 }},
         solution! {18, parser!{ day18::parse_input }, solver!{ day18::part1 }},
+        solution! {18, parser!{ day18::parse_input }, solver!{ day18::part2 }},
         solution! {19, parser!{ day19::parse_input }, solver!{ day19::part1 }},
         solution! {19, parser!{ day19::parse_input }, solver!{ day19::part2 }},
         solution! {20, parser!{ day20::parse_input }, solver!{ day20::part1 }},